@@ -1,6 +1,46 @@
+use crate::grid::Grid;
 use core::fmt::Debug;
+use std::ops::{Index, IndexMut};
 use std::slice::Iter;
 use std::slice::IterMut;
+
+const OFFSETS4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const OFFSETS8: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Reasons why `Array2D::parse_grid` can reject its input.
+/// Lines and columns are 0-based and refer to the input text.
+#[derive(Debug, PartialEq)]
+pub enum ParseGridError<E> {
+    Empty,
+    RaggedLine {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidCell {
+        line: usize,
+        column: usize,
+        error: E,
+    },
+}
+
+/// Result of `Array2D::components`: every passable cell is labelled with
+/// the index of its component, and `sizes` and `cells` are indexed by label.
+#[derive(Debug)]
+pub struct Components {
+    pub labels: Array2D<Option<usize>>,
+    pub sizes: Vec<usize>,
+    pub cells: Vec<Vec<(usize, usize)>>,
+}
 
 #[derive(Clone)]
 pub struct Array2D<T: Default + Clone> {
@@ -17,6 +57,59 @@ impl<T: Default + Clone> Array2D<T> {
             height,
             data,
         }
+    }
+
+    /// Parses one cell per character, skipping empty lines.
+    /// All remaining lines must have the same length.
+    pub fn parse_grid<E, F>(text: &str, mut cell: F) -> Result<Self, ParseGridError<E>>
+    where
+        F: FnMut(char) -> Result<T, E>,
+    {
+        let mut width = None;
+        let mut height = 0;
+        let mut data = Vec::new();
+
+        for (line, s) in text.lines().enumerate().filter(|(_, s)| !s.is_empty()) {
+            let before = data.len();
+            for (column, c) in s.chars().enumerate() {
+                let value = cell(c).map_err(|error| ParseGridError::InvalidCell {
+                    line,
+                    column,
+                    error,
+                })?;
+                data.push(value);
+            }
+
+            let found = data.len() - before;
+            let expected = *width.get_or_insert(found);
+            if found != expected {
+                return Err(ParseGridError::RaggedLine {
+                    line,
+                    expected,
+                    found,
+                });
+            }
+            height += 1;
+        }
+
+        match width {
+            None => Err(ParseGridError::Empty),
+            Some(width) => Ok(Self {
+                width,
+                height,
+                data,
+            }),
+        }
+    }
+
+    /// Renders one character per cell, with a newline after each row.
+    pub fn to_grid_string<F: FnMut(&T) -> char>(&self, mut cell: F) -> String {
+        let mut ret = String::with_capacity((self.width + 1) * self.height);
+        for row in self.data.chunks(self.width.max(1)) {
+            ret.extend(row.iter().map(&mut cell));
+            ret.push('\n');
+        }
+        ret
     }
 
     pub fn width(&self) -> usize {
@@ -49,12 +142,313 @@ impl<T: Default + Clone> Array2D<T> {
         *self.at_mut(pos) = value;
     }
 
+    /// Like `at`, but returns `None` for positions outside the array.
+    pub fn get(&self, pos: (isize, isize)) -> Option<&T> {
+        let pos = self.offset((0, 0), pos)?;
+        Some(self.at(pos))
+    }
+
+    pub fn get_mut(&mut self, pos: (isize, isize)) -> Option<&mut T> {
+        let pos = self.offset((0, 0), pos)?;
+        Some(self.at_mut(pos))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        assert!(y < self.height);
+        &self.data[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        assert!(y < self.height);
+        &mut self.data[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        (0..self.height).map(move |y| self.row(y))
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> + '_ {
+        assert!(x < self.width);
+        self.data.iter().skip(x).step_by(self.width)
+    }
+
+    pub fn column_mut(&mut self, x: usize) -> impl Iterator<Item = &mut T> + '_ {
+        assert!(x < self.width);
+        self.data.iter_mut().skip(x).step_by(self.width)
+    }
+
     pub fn iter(&self) -> Iter<'_, T> {
         self.data.iter()
     }
 
     pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         self.data.iter_mut()
+    }
+
+    /// Iterates row by row, yielding each position along with its value.
+    pub fn indexed_iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        let width = self.width;
+        self.data
+            .iter()
+            .enumerate()
+            .map(move |(i, v)| ((i % width, i / width), v))
+    }
+
+    pub fn indexed_iter_mut(&mut self) -> impl Iterator<Item = ((usize, usize), &mut T)> + '_ {
+        let width = self.width;
+        self.data
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, v)| ((i % width, i / width), v))
+    }
+
+    /// All positions 4-connected to `start` through cells that are `passable`,
+    /// in the order they were reached. Empty if `start` itself isn't passable.
+    pub fn flood_fill<F>(&self, start: (usize, usize), passable: F) -> Vec<(usize, usize)>
+    where
+        F: Fn(&T) -> bool,
+    {
+        let mut visited = Array2D::new(self.width, self.height);
+        self.fill_from(start, &passable, &mut visited)
+    }
+
+    fn fill_from<F>(
+        &self,
+        start: (usize, usize),
+        passable: &F,
+        visited: &mut Array2D<bool>,
+    ) -> Vec<(usize, usize)>
+    where
+        F: Fn(&T) -> bool,
+    {
+        let mut ret = Vec::new();
+        if visited[start] || !passable(self.at(start)) {
+            return ret;
+        }
+
+        visited[start] = true;
+        let mut to_visit = vec![start];
+        while let Some(pos) = to_visit.pop() {
+            ret.push(pos);
+            for n in self.neighbors4(pos) {
+                if !visited[n] && passable(self.at(n)) {
+                    visited[n] = true;
+                    to_visit.push(n);
+                }
+            }
+        }
+        ret
+    }
+
+    /// Splits the `passable` cells into 4-connected components.
+    pub fn components<F>(&self, passable: F) -> Components
+    where
+        F: Fn(&T) -> bool,
+    {
+        let mut visited = Array2D::new(self.width, self.height);
+        let mut labels = Array2D::new(self.width, self.height);
+        let mut cells = Vec::new();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let component = self.fill_from((x, y), &passable, &mut visited);
+                if !component.is_empty() {
+                    for pos in &component {
+                        labels[*pos] = Some(cells.len());
+                    }
+                    cells.push(component);
+                }
+            }
+        }
+
+        let sizes = cells.iter().map(|c| c.len()).collect();
+        Components {
+            labels,
+            sizes,
+            cells,
+        }
+    }
+
+    pub fn transposed(&self) -> Self {
+        let mut ret = Self::new(self.height, self.width);
+        for ((x, y), v) in self.indexed_iter() {
+            ret[(y, x)] = v.clone();
+        }
+        ret
+    }
+
+    /// Mirrors the array left-to-right.
+    pub fn flipped_horizontal(&self) -> Self {
+        let mut ret = self.clone();
+        for y in 0..self.height {
+            ret.row_mut(y).reverse();
+        }
+        ret
+    }
+
+    /// Mirrors the array top-to-bottom.
+    pub fn flipped_vertical(&self) -> Self {
+        let mut ret = Self::new(self.width, self.height);
+        for y in 0..self.height {
+            ret.row_mut(self.height - 1 - y)
+                .clone_from_slice(self.row(y));
+        }
+        ret
+    }
+
+    /// Rotates the array by 90 degrees clockwise.
+    pub fn rotated_cw(&self) -> Self {
+        self.transposed().flipped_horizontal()
+    }
+
+    /// Rotates the array by 90 degrees counter-clockwise.
+    pub fn rotated_ccw(&self) -> Self {
+        self.transposed().flipped_vertical()
+    }
+
+    /// Copies the `width` x `height` region starting at `pos`.
+    pub fn cropped(&self, pos: (usize, usize), width: usize, height: usize) -> Self {
+        assert!(pos.0 + width <= self.width);
+        assert!(pos.1 + height <= self.height);
+
+        let mut ret = Self::new(width, height);
+        for y in 0..height {
+            let row = &self.row(pos.1 + y)[pos.0..pos.0 + width];
+            ret.row_mut(y).clone_from_slice(row);
+        }
+        ret
+    }
+
+    /// Surrounds the array with `border` cells of `fill` on every side.
+    pub fn padded(&self, border: usize, fill: T) -> Self {
+        let mut ret = Self::new(self.width + 2 * border, self.height + 2 * border);
+        ret.fill(fill);
+        ret.blit(self, (border, border));
+        ret
+    }
+
+    /// Combines every cell of `other` into the cell of `self` it covers
+    /// when placing `other` with its top-left corner at `pos`.
+    pub fn merge<F>(&mut self, other: &Self, pos: (usize, usize), mut f: F)
+    where
+        F: FnMut(&mut T, &T),
+    {
+        assert!(pos.0 + other.width <= self.width);
+        assert!(pos.1 + other.height <= self.height);
+
+        for y in 0..other.height {
+            let dst = &mut self.row_mut(pos.1 + y)[pos.0..pos.0 + other.width];
+            for (d, s) in dst.iter_mut().zip(other.row(y)) {
+                f(d, s);
+            }
+        }
+    }
+
+    /// Overwrites the region covered by `other` placed at `pos`.
+    pub fn blit(&mut self, other: &Self, pos: (usize, usize)) {
+        self.merge(other, pos, |d, s| d.clone_from(s));
+    }
+
+    /// Moves `pos` by `delta`, or returns `None` if that leaves the array.
+    pub fn offset(&self, pos: (usize, usize), delta: (isize, isize)) -> Option<(usize, usize)> {
+        let x = pos.0.checked_add_signed(delta.0)?;
+        let y = pos.1.checked_add_signed(delta.1)?;
+        if x < self.width && y < self.height {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// Moves `pos` by `delta`, wrapping around the edges of the array.
+    pub fn offset_wrapping(&self, pos: (usize, usize), delta: (isize, isize)) -> (usize, usize) {
+        let x = (pos.0 as isize + delta.0).rem_euclid(self.width as isize);
+        let y = (pos.1 as isize + delta.1).rem_euclid(self.height as isize);
+        (x as usize, y as usize)
+    }
+
+    /// The horizontally and vertically adjacent positions inside the array.
+    pub fn neighbors4(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        OFFSETS4.iter().filter_map(move |d| self.offset(pos, *d))
+    }
+
+    /// Like `neighbors4`, but including diagonals.
+    pub fn neighbors8(&self, pos: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        OFFSETS8.iter().filter_map(move |d| self.offset(pos, *d))
+    }
+
+    /// Like `neighbors4`, but treating the array as a torus.
+    /// On arrays narrower than 3 cells, positions may be repeated.
+    pub fn neighbors4_wrapping(
+        &self,
+        pos: (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        OFFSETS4.iter().map(move |d| self.offset_wrapping(pos, *d))
+    }
+
+    /// Like `neighbors8`, but treating the array as a torus.
+    /// On arrays narrower than 3 cells, positions may be repeated.
+    pub fn neighbors8_wrapping(
+        &self,
+        pos: (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        OFFSETS8.iter().map(move |d| self.offset_wrapping(pos, *d))
+    }
+
+    pub fn neighbor_values4(
+        &self,
+        pos: (usize, usize),
+    ) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        self.neighbors4(pos).map(move |p| (p, self.at(p)))
+    }
+
+    pub fn neighbor_values8(
+        &self,
+        pos: (usize, usize),
+    ) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        self.neighbors8(pos).map(move |p| (p, self.at(p)))
+    }
+}
+
+impl<T: Copy + Default + PartialEq> Grid<T> for Array2D<T> {
+    fn new(width: usize, height: usize) -> Self {
+        Array2D::new(width, height)
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn value(&self, pos: (usize, usize)) -> T {
+        *self.at(pos)
+    }
+
+    fn set(&mut self, pos: (usize, usize), value: T) {
+        Array2D::set(self, pos, value)
+    }
+
+    fn occupied(&self) -> impl Iterator<Item = ((usize, usize), T)> + '_ {
+        self.indexed_iter()
+            .filter(|(_, v)| **v != T::default())
+            .map(|(pos, v)| (pos, *v))
+    }
+}
+
+impl<T: Default + Clone> Index<(usize, usize)> for Array2D<T> {
+    type Output = T;
+
+    fn index(&self, pos: (usize, usize)) -> &T {
+        self.at(pos)
+    }
+}
+
+impl<T: Default + Clone> IndexMut<(usize, usize)> for Array2D<T> {
+    fn index_mut(&mut self, pos: (usize, usize)) -> &mut T {
+        self.at_mut(pos)
     }
 }
 
@@ -70,3 +464,67 @@ impl<T: Default + Debug + Clone> Debug for Array2D<T> {
         list.finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Array2D;
+
+    #[test]
+    fn test_transforms() {
+        let grid = Array2D::parse_grid("abc\ndef\n", Ok::<char, ()>).unwrap();
+        let render = |g: &Array2D<char>| g.to_grid_string(|c| *c);
+
+        assert_eq!(render(&grid.transposed()), "ad\nbe\ncf\n");
+        assert_eq!(render(&grid.flipped_horizontal()), "cba\nfed\n");
+        assert_eq!(render(&grid.flipped_vertical()), "def\nabc\n");
+        assert_eq!(render(&grid.rotated_cw()), "da\neb\nfc\n");
+        assert_eq!(render(&grid.rotated_ccw()), "cf\nbe\nad\n");
+        assert_eq!(render(&grid.cropped((1, 0), 2, 2)), "bc\nef\n");
+        assert_eq!(render(&grid.padded(1, '.')), ".....\n.abc.\n.def.\n.....\n");
+    }
+
+    #[test]
+    fn test_components() {
+        let input = "2199943210\n3987894921\n9856789892\n8767896789\n9899965678";
+        let heights = Array2D::parse_grid(input, |c| c.to_digit(10).ok_or(c)).unwrap();
+
+        let components = heights.components(|h| *h < 9);
+        assert_eq!(components.sizes, vec![3, 9, 14, 9]);
+        assert_eq!(components.labels[(0, 0)], Some(0));
+        assert_eq!(components.labels[(2, 0)], None);
+        assert_eq!(components.cells[1].len(), 9);
+
+        assert_eq!(heights.flood_fill((9, 4), |h| *h < 9).len(), 9);
+        assert!(heights.flood_fill((2, 0), |h| *h < 9).is_empty());
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = Array2D::parse_grid("abcd\nefgh\nijkl\n", Ok::<char, ()>).unwrap();
+        let letters = |it: &mut dyn Iterator<Item = (usize, usize)>| -> String {
+            let mut ret: Vec<char> = it.map(|p| grid[p]).collect();
+            ret.sort();
+            ret.into_iter().collect()
+        };
+
+        // corner, edge and inner cells
+        assert_eq!(letters(&mut grid.neighbors4((0, 0))), "be");
+        assert_eq!(letters(&mut grid.neighbors8((0, 0))), "bef");
+        assert_eq!(letters(&mut grid.neighbors4((1, 0))), "acf");
+        assert_eq!(letters(&mut grid.neighbors8((1, 0))), "acefg");
+        assert_eq!(letters(&mut grid.neighbors4((1, 1))), "begj");
+        assert_eq!(letters(&mut grid.neighbors8((1, 1))), "abcegijk");
+        assert_eq!(letters(&mut grid.neighbors8((3, 2))), "ghk");
+
+        // wrapping around the edges
+        assert_eq!(letters(&mut grid.neighbors4_wrapping((0, 0))), "bdei");
+        assert_eq!(letters(&mut grid.neighbors8_wrapping((0, 0))), "bdefhijl");
+        assert_eq!(letters(&mut grid.neighbors4_wrapping((3, 2))), "dhik");
+        assert_eq!(letters(&mut grid.neighbors8_wrapping((1, 1))), "abcegijk");
+
+        let values: Vec<char> = grid.neighbor_values4((3, 0)).map(|v| *v.1).collect();
+        assert_eq!(values, vec!['c', 'h']);
+        assert_eq!(grid.offset_wrapping((0, 0), (-1, -5)), (3, 1));
+        assert_eq!(grid.offset((0, 0), (-1, 0)), None);
+    }
+}
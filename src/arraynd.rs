@@ -0,0 +1,164 @@
+use std::ops::{Index, IndexMut};
+use std::slice::Iter;
+use std::slice::IterMut;
+
+/// A dense `D`-dimensional array, stored with the first coordinate varying fastest.
+#[derive(Clone)]
+pub struct ArrayND<T: Default + Clone, const D: usize> {
+    dims: [usize; D],
+    data: Vec<T>,
+}
+
+impl<T: Default + Clone, const D: usize> ArrayND<T, D> {
+    pub fn new(dims: [usize; D]) -> Self {
+        let data = vec![T::default(); dims.iter().product()];
+        Self { dims, data }
+    }
+
+    pub fn dims(&self) -> [usize; D] {
+        self.dims
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn fill(&mut self, value: T) {
+        for v in &mut self.data {
+            *v = value.clone();
+        }
+    }
+
+    fn index_of(&self, pos: [usize; D]) -> usize {
+        let mut idx = 0;
+        for d in (0..D).rev() {
+            debug_assert!(pos[d] < self.dims[d]);
+            idx = idx * self.dims[d] + pos[d];
+        }
+        idx
+    }
+
+    fn pos_of(&self, mut idx: usize) -> [usize; D] {
+        let mut pos = [0; D];
+        for (p, dim) in pos.iter_mut().zip(self.dims) {
+            *p = idx % dim;
+            idx /= dim;
+        }
+        pos
+    }
+
+    pub fn at(&self, pos: [usize; D]) -> &T {
+        &self.data[self.index_of(pos)]
+    }
+
+    pub fn at_mut(&mut self, pos: [usize; D]) -> &mut T {
+        let idx = self.index_of(pos);
+        &mut self.data[idx]
+    }
+
+    pub fn set(&mut self, pos: [usize; D], value: T) {
+        *self.at_mut(pos) = value;
+    }
+
+    /// Like `at`, but returns `None` for positions outside the array.
+    pub fn get(&self, pos: [isize; D]) -> Option<&T> {
+        let pos = self.offset([0; D], pos)?;
+        Some(self.at(pos))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.data.iter_mut()
+    }
+
+    pub fn indexed_iter(&self) -> impl Iterator<Item = ([usize; D], &T)> + '_ {
+        self.data
+            .iter()
+            .enumerate()
+            .map(move |(i, v)| (self.pos_of(i), v))
+    }
+
+    /// Moves `pos` by `delta`, or returns `None` if that leaves the array.
+    pub fn offset(&self, pos: [usize; D], delta: [isize; D]) -> Option<[usize; D]> {
+        let mut ret = [0; D];
+        for d in 0..D {
+            ret[d] = pos[d].checked_add_signed(delta[d])?;
+            if ret[d] >= self.dims[d] {
+                return None;
+            }
+        }
+        Some(ret)
+    }
+
+    /// The `2 * D` positions inside the array that share a face with `pos`.
+    pub fn neighbors(&self, pos: [usize; D]) -> impl Iterator<Item = [usize; D]> + '_ {
+        (0..2 * D).filter_map(move |i| {
+            let mut delta = [0; D];
+            delta[i / 2] = if i % 2 == 0 { -1 } else { 1 };
+            self.offset(pos, delta)
+        })
+    }
+
+    /// The up to `3^D - 1` positions inside the array that touch `pos`,
+    /// including diagonals.
+    pub fn neighbors_all(&self, pos: [usize; D]) -> impl Iterator<Item = [usize; D]> + '_ {
+        let count = 3usize.pow(D as u32);
+        (0..count)
+            .filter(move |i| *i != count / 2)
+            .filter_map(move |mut i| {
+                let mut delta = [0; D];
+                for d in delta.iter_mut() {
+                    *d = (i % 3) as isize - 1;
+                    i /= 3;
+                }
+                self.offset(pos, delta)
+            })
+    }
+}
+
+impl<T: Default + Clone, const D: usize> Index<[usize; D]> for ArrayND<T, D> {
+    type Output = T;
+
+    fn index(&self, pos: [usize; D]) -> &T {
+        self.at(pos)
+    }
+}
+
+impl<T: Default + Clone, const D: usize> IndexMut<[usize; D]> for ArrayND<T, D> {
+    fn index_mut(&mut self, pos: [usize; D]) -> &mut T {
+        self.at_mut(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArrayND;
+
+    #[test]
+    fn test_neighbors() {
+        let mut array = ArrayND::<usize, 3>::new([3, 4, 5]);
+        for (i, v) in array.iter_mut().enumerate() {
+            *v = i;
+        }
+        assert_eq!(array[[2, 1, 0]], 5);
+        assert_eq!(array[[0, 0, 1]], 12);
+        assert_eq!(array.indexed_iter().nth(17).unwrap().0, [2, 1, 1]);
+
+        assert_eq!(array.neighbors([1, 1, 1]).count(), 6);
+        assert_eq!(array.neighbors([0, 0, 0]).count(), 3);
+        assert_eq!(array.neighbors_all([1, 1, 1]).count(), 26);
+        assert_eq!(array.neighbors_all([0, 0, 0]).count(), 7);
+        assert_eq!(array.get([-1, 0, 0]), None);
+        assert_eq!(array.get([2, 3, 4]), Some(&59));
+
+        let hyper = ArrayND::<bool, 4>::new([3, 3, 3, 3]);
+        assert_eq!(hyper.neighbors_all([1, 1, 1, 1]).count(), 80);
+    }
+}
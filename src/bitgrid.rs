@@ -0,0 +1,426 @@
+use crate::array2d::Array2D;
+use crate::grid::Grid;
+use core::fmt::Debug;
+use std::ops::{BitAndAssign, BitOrAssign, BitXorAssign};
+
+const BITS: usize = u64::BITS as usize;
+
+/// A boolean grid storing each row as packed 64-bit words.
+/// Bit `x % 64` of word `x / 64` holds column `x`; unused bits at the
+/// end of each row are always kept at zero.
+#[derive(Clone, PartialEq, Eq)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    stride: usize,
+    data: Vec<u64>,
+}
+
+fn mask(bits: usize) -> u64 {
+    if bits >= BITS {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    }
+}
+
+/// Reads the 64 bits starting at bit `offset` of `row`, padding with zeros.
+fn read_bits(row: &[u64], offset: usize) -> u64 {
+    let word = offset / BITS;
+    let shift = offset % BITS;
+    let lo = row.get(word).copied().unwrap_or(0) >> shift;
+    let hi = if shift > 0 {
+        row.get(word + 1).copied().unwrap_or(0) << (BITS - shift)
+    } else {
+        0
+    };
+    lo | hi
+}
+
+impl BitGrid {
+    pub fn new(width: usize, height: usize) -> Self {
+        let stride = width.div_ceil(BITS);
+        let data = vec![0; stride * height];
+        Self {
+            width,
+            height,
+            stride,
+            data,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn fill(&mut self, value: bool) {
+        let word = if value { u64::MAX } else { 0 };
+        let last = mask(self.width - (self.stride.max(1) - 1) * BITS);
+        for y in 0..self.height {
+            let row = self.row_words_mut(y);
+            row.fill(word);
+            if let Some(w) = row.last_mut() {
+                *w &= last;
+            }
+        }
+    }
+
+    pub fn at(&self, pos: (usize, usize)) -> bool {
+        debug_assert!(pos.0 < self.width);
+        debug_assert!(pos.1 < self.height);
+        let word = self.data[pos.1 * self.stride + pos.0 / BITS];
+        (word >> (pos.0 % BITS)) & 1 != 0
+    }
+
+    pub fn set(&mut self, pos: (usize, usize), value: bool) {
+        debug_assert!(pos.0 < self.width);
+        debug_assert!(pos.1 < self.height);
+        let word = &mut self.data[pos.1 * self.stride + pos.0 / BITS];
+        let bit = 1 << (pos.0 % BITS);
+        if value {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    /// Like `at`, but returns `None` for positions outside the grid.
+    pub fn get(&self, pos: (isize, isize)) -> Option<bool> {
+        if pos.0 < 0 || pos.1 < 0 {
+            return None;
+        }
+        let pos = (pos.0 as usize, pos.1 as usize);
+        if pos.0 < self.width && pos.1 < self.height {
+            Some(self.at(pos))
+        } else {
+            None
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        self.indexed_iter().map(|(_, v)| v)
+    }
+
+    pub fn indexed_iter(&self) -> impl Iterator<Item = ((usize, usize), bool)> + '_ {
+        (0..self.height).flat_map(move |y| (0..self.width).map(move |x| ((x, y), self.at((x, y)))))
+    }
+
+    /// Number of set cells.
+    pub fn count_ones(&self) -> usize {
+        self.data.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn row_words(&self, y: usize) -> &[u64] {
+        assert!(y < self.height);
+        &self.data[y * self.stride..(y + 1) * self.stride]
+    }
+
+    pub fn row_words_mut(&mut self, y: usize) -> &mut [u64] {
+        assert!(y < self.height);
+        &mut self.data[y * self.stride..(y + 1) * self.stride]
+    }
+
+    /// Reads 64 cells of row `y`, starting at column `x`, into one word.
+    /// Cells beyond the right edge read as zero.
+    pub fn read_word(&self, x: usize, y: usize) -> u64 {
+        read_bits(self.row_words(y), x)
+    }
+
+    /// Combines row `other_y` of `other` into row `y`, one word at a time.
+    pub fn combine_row<F>(&mut self, y: usize, other: &BitGrid, other_y: usize, op: F)
+    where
+        F: Fn(u64, u64) -> u64,
+    {
+        assert!(self.width == other.width);
+        let last = mask(self.width - (self.stride.max(1) - 1) * BITS);
+        let src = other.row_words(other_y);
+        let dst = self.row_words_mut(y);
+        for (d, s) in dst.iter_mut().zip(src) {
+            *d = op(*d, *s);
+        }
+        if let Some(d) = dst.last_mut() {
+            *d &= last;
+        }
+    }
+
+    pub fn and_row(&mut self, y: usize, other: &BitGrid, other_y: usize) {
+        self.combine_row(y, other, other_y, |a, b| a & b);
+    }
+
+    pub fn or_row(&mut self, y: usize, other: &BitGrid, other_y: usize) {
+        self.combine_row(y, other, other_y, |a, b| a | b);
+    }
+
+    pub fn xor_row(&mut self, y: usize, other: &BitGrid, other_y: usize) {
+        self.combine_row(y, other, other_y, |a, b| a ^ b);
+    }
+
+    /// Combines every cell of `other` into the cell of `self` it covers
+    /// when placing `other` with its top-left corner at `pos`.
+    /// Cells outside of that region are left untouched.
+    pub fn merge<F>(&mut self, other: &BitGrid, pos: (usize, usize), op: F)
+    where
+        F: Fn(u64, u64) -> u64,
+    {
+        assert!(pos.0 + other.width <= self.width);
+        assert!(pos.1 + other.height <= self.height);
+
+        for y in 0..other.height {
+            let src = other.row_words(y);
+            let dst = self.row_words_mut(pos.1 + y);
+
+            // walk the destination words overlapped by the source row
+            let first = pos.0 / BITS;
+            let end = pos.0 + other.width;
+            let mut x = first * BITS;
+            while x < end {
+                let lo = x.max(pos.0);
+                let covered = mask(end.min(x + BITS) - lo) << (lo - x);
+                let bits = if x >= pos.0 {
+                    read_bits(src, x - pos.0)
+                } else {
+                    read_bits(src, 0) << (pos.0 - x)
+                };
+                let d = &mut dst[x / BITS];
+                *d = (*d & !covered) | (op(*d, bits) & covered);
+                x += BITS;
+            }
+        }
+    }
+
+    /// Overwrites the region covered by `other` placed at `pos`.
+    pub fn blit(&mut self, other: &BitGrid, pos: (usize, usize)) {
+        self.merge(other, pos, |_, b| b);
+    }
+
+    /// Copies the `width` x `height` region starting at `pos`.
+    pub fn cropped(&self, pos: (usize, usize), width: usize, height: usize) -> Self {
+        assert!(pos.0 + width <= self.width);
+        assert!(pos.1 + height <= self.height);
+
+        let mut ret = Self::new(width, height);
+        if width == 0 {
+            return ret;
+        }
+        let last = mask(width - (ret.stride - 1) * BITS);
+        for y in 0..height {
+            let src = self.row_words(pos.1 + y);
+            let dst = ret.row_words_mut(y);
+            for (i, d) in dst.iter_mut().enumerate() {
+                *d = read_bits(src, pos.0 + i * BITS);
+            }
+            dst[dst.len() - 1] &= last;
+        }
+        ret
+    }
+
+    /// Surrounds the grid with `border` cells of `fill` on every side.
+    pub fn padded(&self, border: usize, fill: bool) -> Self {
+        let mut ret = Self::new(self.width + 2 * border, self.height + 2 * border);
+        ret.fill(fill);
+        ret.blit(self, (border, border));
+        ret
+    }
+
+    /// Moves all cells by `(dx, dy)`. Cells moved past the edges are lost,
+    /// cells moved in from outside are cleared.
+    pub fn shifted(&self, dx: isize, dy: isize) -> Self {
+        let mut ret = Self::new(self.width, self.height);
+        let w = self.width.saturating_sub(dx.unsigned_abs());
+        let h = self.height.saturating_sub(dy.unsigned_abs());
+        if w > 0 && h > 0 {
+            let src = (dx.min(0).unsigned_abs(), dy.min(0).unsigned_abs());
+            let dst = (dx.max(0) as usize, dy.max(0) as usize);
+            ret.blit(&self.cropped(src, w, h), dst);
+        }
+        ret
+    }
+
+    /// Mirrors the grid left-to-right.
+    pub fn flipped_horizontal(&self) -> Self {
+        let mut ret = Self::new(self.width, self.height);
+        let padding = self.stride * BITS - self.width;
+        let mut reversed = vec![0; self.stride];
+        for y in 0..self.height {
+            for (r, s) in reversed.iter_mut().zip(self.row_words(y).iter().rev()) {
+                *r = s.reverse_bits();
+            }
+            for (i, d) in ret.row_words_mut(y).iter_mut().enumerate() {
+                *d = read_bits(&reversed, padding + i * BITS);
+            }
+        }
+        ret
+    }
+
+    /// Mirrors the grid top-to-bottom.
+    pub fn flipped_vertical(&self) -> Self {
+        let mut ret = Self::new(self.width, self.height);
+        for y in 0..self.height {
+            let src = self.row_words(self.height - 1 - y);
+            ret.row_words_mut(y).copy_from_slice(src);
+        }
+        ret
+    }
+
+    pub fn to_array2d(&self) -> Array2D<bool> {
+        let mut ret = Array2D::new(self.width, self.height);
+        for (pos, v) in self.indexed_iter() {
+            ret[pos] = v;
+        }
+        ret
+    }
+}
+
+impl Grid<bool> for BitGrid {
+    fn new(width: usize, height: usize) -> Self {
+        BitGrid::new(width, height)
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn value(&self, pos: (usize, usize)) -> bool {
+        self.at(pos)
+    }
+
+    fn set(&mut self, pos: (usize, usize), value: bool) {
+        BitGrid::set(self, pos, value)
+    }
+
+    fn occupied(&self) -> impl Iterator<Item = ((usize, usize), bool)> + '_ {
+        self.indexed_iter().filter(|(_, v)| *v)
+    }
+}
+
+impl From<&Array2D<bool>> for BitGrid {
+    fn from(array: &Array2D<bool>) -> Self {
+        let mut ret = Self::new(array.width(), array.height());
+        for (pos, v) in array.indexed_iter() {
+            ret.set(pos, *v);
+        }
+        ret
+    }
+}
+
+impl From<&BitGrid> for Array2D<bool> {
+    fn from(grid: &BitGrid) -> Self {
+        grid.to_array2d()
+    }
+}
+
+impl BitAndAssign<&BitGrid> for BitGrid {
+    fn bitand_assign(&mut self, rhs: &BitGrid) {
+        assert!(self.height == rhs.height);
+        for y in 0..self.height {
+            self.and_row(y, rhs, y);
+        }
+    }
+}
+
+impl BitOrAssign<&BitGrid> for BitGrid {
+    fn bitor_assign(&mut self, rhs: &BitGrid) {
+        assert!(self.height == rhs.height);
+        for y in 0..self.height {
+            self.or_row(y, rhs, y);
+        }
+    }
+}
+
+impl BitXorAssign<&BitGrid> for BitGrid {
+    fn bitxor_assign(&mut self, rhs: &BitGrid) {
+        assert!(self.height == rhs.height);
+        for y in 0..self.height {
+            self.xor_row(y, rhs, y);
+        }
+    }
+}
+
+impl Debug for BitGrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut list = f.debug_list();
+        for y in 0..self.height {
+            let row: String = (0..self.width)
+                .map(|x| if self.at((x, y)) { '#' } else { '.' })
+                .collect();
+            list.entry(&row);
+        }
+        list.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitGrid;
+    use crate::array2d::Array2D;
+
+    fn grid(width: usize, height: usize, seed: usize) -> Array2D<bool> {
+        let mut ret = Array2D::new(width, height);
+        for (i, v) in ret.iter_mut().enumerate() {
+            *v = (i * 7 + seed) % 5 < 2;
+        }
+        ret
+    }
+
+    #[test]
+    fn test_matches_array2d() {
+        for width in [1, 3, 63, 64, 65, 130] {
+            let array = grid(width, 3, width);
+            let bits = BitGrid::from(&array);
+            assert_eq!(
+                bits.to_array2d().iter().collect::<Vec<_>>(),
+                array.iter().collect::<Vec<_>>()
+            );
+            assert_eq!(bits.count_ones(), array.iter().filter(|b| **b).count());
+
+            let flipped = BitGrid::from(&array.flipped_horizontal());
+            assert_eq!(bits.flipped_horizontal(), flipped, "width={}", width);
+            let flipped = BitGrid::from(&array.flipped_vertical());
+            assert_eq!(bits.flipped_vertical(), flipped, "width={}", width);
+
+            let padded = BitGrid::from(&array.padded(2, true));
+            assert_eq!(bits.padded(2, true), padded, "width={}", width);
+
+            let w = width.div_ceil(2);
+            let cropped = BitGrid::from(&array.cropped((width - w, 1), w, 2));
+            assert_eq!(
+                bits.cropped((width - w, 1), w, 2),
+                cropped,
+                "width={}",
+                width
+            );
+        }
+    }
+
+    #[test]
+    fn test_shift_and_ops() {
+        let mut bits = BitGrid::new(70, 2);
+        bits.set((0, 0), true);
+        bits.set((69, 1), true);
+
+        let shifted = bits.shifted(68, 1);
+        assert_eq!(shifted.count_ones(), 1);
+        assert!(shifted.at((68, 1)));
+
+        let shifted = bits.shifted(-69, -1);
+        assert_eq!(shifted.count_ones(), 1);
+        assert!(shifted.at((0, 0)));
+
+        let mut other = BitGrid::new(70, 2);
+        other.fill(true);
+        other ^= &bits;
+        assert_eq!(other.count_ones(), 138);
+        other |= &bits;
+        assert_eq!(other.count_ones(), 140);
+        other &= &bits;
+        assert_eq!(other, bits);
+    }
+}
@@ -0,0 +1,368 @@
+/// An opening and closing delimiter, with what they are worth when scoring
+/// corrupted and incomplete lines.
+#[derive(Clone, Copy)]
+pub struct Pair {
+    pub open: char,
+    pub close: char,
+    /// Score of an unexpected `close`.
+    pub corrupted: usize,
+    /// Score of `close` in a completion string.
+    pub completion: usize,
+}
+
+/// Why a line isn't properly nested. Offsets are in bytes.
+#[derive(Debug, PartialEq)]
+pub enum DelimiterError {
+    /// `found` at `offset` doesn't close the innermost open delimiter,
+    /// which `expected` would, if anything is open at all.
+    Corrupted {
+        offset: usize,
+        expected: Option<char>,
+        found: char,
+    },
+    /// The line ended while delimiters were open; appending `completion`
+    /// closes them.
+    Incomplete { completion: String },
+}
+
+/// How `Delimiters::check_all` carries on after a corrupted character.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Skip the character, as if it was deleted.
+    Delete,
+    /// Treat it as the expected closer. Stray closers with nothing open
+    /// are skipped.
+    Substitute,
+}
+
+/// A way of making a line properly nested with as few single-character
+/// insertions, deletions and substitutions as possible.
+#[derive(Debug, PartialEq)]
+pub struct Repair {
+    pub edits: usize,
+    pub fixed: String,
+}
+
+/// The edit chosen for a range of the line in `Delimiters::repair`.
+#[derive(Clone, Copy)]
+enum Edit {
+    Empty,
+    Delete,
+    /// Keep an opener and append its closer after the rest of the range.
+    Close,
+    /// Put the opener right in front of a closer.
+    Open,
+    /// Pair the first character with the one at this index, using these
+    /// delimiters (substituting whichever doesn't match).
+    Match(usize, char, char),
+}
+
+/// Checks that delimiters from a table of pairs are properly nested.
+pub struct Delimiters {
+    pairs: Vec<Pair>,
+    /// The completion score is read as a number in this base.
+    completion_base: usize,
+}
+
+impl Delimiters {
+    pub fn new(pairs: &[Pair], completion_base: usize) -> Self {
+        let pairs = pairs.to_vec();
+        Self {
+            pairs,
+            completion_base,
+        }
+    }
+
+    fn opened(&self, c: char) -> Option<&Pair> {
+        self.pairs.iter().find(|p| p.open == c)
+    }
+
+    fn closed(&self, c: char) -> Option<&Pair> {
+        self.pairs.iter().find(|p| p.close == c)
+    }
+
+    /// Checks `line`, treating any character that doesn't open a pair as
+    /// a closer, so unknown characters show up as corruption.
+    pub fn check(&self, line: &str) -> Result<(), DelimiterError> {
+        let mut stack = Vec::new();
+        for (offset, c) in line.char_indices() {
+            if let Some(pair) = self.opened(c) {
+                stack.push(pair.close);
+            } else if stack.last() == Some(&c) {
+                stack.pop();
+            } else {
+                let expected = stack.last().copied();
+                return Err(DelimiterError::Corrupted {
+                    offset,
+                    expected,
+                    found: c,
+                });
+            }
+        }
+
+        if stack.is_empty() {
+            Ok(())
+        } else {
+            let completion = stack.iter().rev().collect();
+            Err(DelimiterError::Incomplete { completion })
+        }
+    }
+
+    /// Like `check`, but keeps going after corrupted characters as `recovery`
+    /// says, returning all errors in the order they were found.
+    pub fn check_all(&self, line: &str, recovery: Recovery) -> Vec<DelimiterError> {
+        let mut errors = Vec::new();
+        let mut stack = Vec::new();
+        for (offset, c) in line.char_indices() {
+            if let Some(pair) = self.opened(c) {
+                stack.push(pair.close);
+            } else if stack.last() == Some(&c) {
+                stack.pop();
+            } else {
+                let expected = stack.last().copied();
+                errors.push(DelimiterError::Corrupted {
+                    offset,
+                    expected,
+                    found: c,
+                });
+                if recovery == Recovery::Substitute {
+                    stack.pop();
+                }
+            }
+        }
+
+        if !stack.is_empty() {
+            let completion = stack.iter().rev().collect();
+            errors.push(DelimiterError::Incomplete { completion });
+        }
+        errors
+    }
+
+    /// Finds a minimal repair of `line`. Corrupted characters are preferably
+    /// substituted and missing closers appended, rather than deleting anything.
+    ///
+    /// This tries every way of pairing up characters, taking O(n³ · pairs)
+    /// time and O(n²) memory for a line of n characters, so it is only meant
+    /// for lines of a few hundred characters.
+    pub fn repair(&self, line: &str) -> Repair {
+        let chars: Vec<char> = line.chars().collect();
+        let n = chars.len();
+
+        // cost[i][j] is the number of edits needed to fix chars[i..j]
+        let mut cost = vec![vec![0; n + 1]; n + 1];
+        let mut edit = vec![vec![Edit::Empty; n + 1]; n + 1];
+        for len in 1..=n {
+            for i in 0..=(n - len) {
+                let j = i + len;
+                let c = chars[i];
+
+                // on ties, the first candidate wins: pairing up characters,
+                // then inserting a missing delimiter, then deleting
+                let mut best = (usize::MAX, Edit::Empty);
+                let mut consider = |edits: usize, e: Edit| {
+                    if edits < best.0 {
+                        best = (edits, e);
+                    }
+                };
+                for k in (i + 1)..j {
+                    let inner = cost[i + 1][k] + cost[k + 1][j];
+                    for pair in &self.pairs {
+                        let edits = (c != pair.open) as usize + (chars[k] != pair.close) as usize;
+                        consider(inner + edits, Edit::Match(k, pair.open, pair.close));
+                    }
+                }
+                if self.opened(c).is_some() {
+                    consider(1 + cost[i + 1][j], Edit::Close);
+                } else if self.closed(c).is_some() {
+                    consider(1 + cost[i + 1][j], Edit::Open);
+                }
+                consider(1 + cost[i + 1][j], Edit::Delete);
+
+                cost[i][j] = best.0;
+                edit[i][j] = best.1;
+            }
+        }
+
+        Repair {
+            edits: cost[0][n],
+            fixed: self.rebuild(&chars, &edit),
+        }
+    }
+
+    /// Applies the edits chosen by `repair` to the whole line.
+    fn rebuild(&self, chars: &[char], edit: &[Vec<Edit>]) -> String {
+        // ranges still to rebuild and closers still to write, last one first,
+        // so that long lines don't need deep recursion
+        enum Todo {
+            Range(usize, usize),
+            Push(char),
+        }
+
+        let mut out = String::new();
+        let mut todo = vec![Todo::Range(0, chars.len())];
+        while let Some(next) = todo.pop() {
+            let (i, j) = match next {
+                Todo::Push(c) => {
+                    out.push(c);
+                    continue;
+                }
+                Todo::Range(i, j) => (i, j),
+            };
+            match edit[i][j] {
+                Edit::Empty => {}
+                Edit::Delete => todo.push(Todo::Range(i + 1, j)),
+                Edit::Close => {
+                    out.push(chars[i]);
+                    todo.push(Todo::Push(self.opened(chars[i]).unwrap().close));
+                    todo.push(Todo::Range(i + 1, j));
+                }
+                Edit::Open => {
+                    out.push(self.closed(chars[i]).unwrap().open);
+                    out.push(chars[i]);
+                    todo.push(Todo::Range(i + 1, j));
+                }
+                Edit::Match(k, open, close) => {
+                    out.push(open);
+                    todo.push(Todo::Range(k + 1, j));
+                    todo.push(Todo::Push(close));
+                    todo.push(Todo::Range(i + 1, k));
+                }
+            }
+        }
+        out
+    }
+
+    /// Score of finding `c` where it doesn't belong, 0 for unknown characters.
+    pub fn corrupted_score(&self, c: char) -> usize {
+        self.closed(c).map_or(0, |p| p.corrupted)
+    }
+
+    pub fn completion_score(&self, completion: &str) -> usize {
+        completion.chars().fold(0, |score, c| {
+            score * self.completion_base + self.closed(c).map_or(0, |p| p.completion)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DelimiterError, Delimiters, Pair, Recovery, Repair};
+
+    #[test]
+    fn test_check() {
+        let pair = |open, close, corrupted, completion| Pair {
+            open,
+            close,
+            corrupted,
+            completion,
+        };
+        let delimiters = Delimiters::new(&[pair('(', ')', 1, 1), pair('«', '»', 10, 2)], 3);
+
+        assert_eq!(delimiters.check("(«»)()"), Ok(()));
+        assert_eq!(
+            delimiters.check("(««)"),
+            Err(DelimiterError::Corrupted {
+                offset: 5,
+                expected: Some('»'),
+                found: ')'
+            })
+        );
+        assert_eq!(
+            delimiters.check("()x"),
+            Err(DelimiterError::Corrupted {
+                offset: 2,
+                expected: None,
+                found: 'x'
+            })
+        );
+        assert_eq!(
+            delimiters.check("(«()"),
+            Err(DelimiterError::Incomplete {
+                completion: String::from("»)")
+            })
+        );
+
+        let line = "(«)»)x";
+        assert_eq!(
+            delimiters.check_all(line, Recovery::Delete),
+            vec![
+                DelimiterError::Corrupted {
+                    offset: 3,
+                    expected: Some('»'),
+                    found: ')'
+                },
+                DelimiterError::Corrupted {
+                    offset: 7,
+                    expected: None,
+                    found: 'x'
+                },
+            ]
+        );
+        assert_eq!(
+            delimiters.check_all(line, Recovery::Substitute),
+            vec![
+                DelimiterError::Corrupted {
+                    offset: 3,
+                    expected: Some('»'),
+                    found: ')'
+                },
+                DelimiterError::Corrupted {
+                    offset: 4,
+                    expected: Some(')'),
+                    found: '»'
+                },
+                DelimiterError::Corrupted {
+                    offset: 6,
+                    expected: None,
+                    found: ')'
+                },
+                DelimiterError::Corrupted {
+                    offset: 7,
+                    expected: None,
+                    found: 'x'
+                },
+            ]
+        );
+
+        assert_eq!(delimiters.corrupted_score('»'), 10);
+        assert_eq!(delimiters.corrupted_score('x'), 0);
+        assert_eq!(delimiters.completion_score("»)»"), 2 * 9 + 3 + 2);
+    }
+
+    #[test]
+    fn test_repair() {
+        let pair = |open, close| Pair {
+            open,
+            close,
+            corrupted: 0,
+            completion: 0,
+        };
+        let delimiters = Delimiters::new(&[pair('(', ')'), pair('[', ']')], 5);
+        let repair = |line| delimiters.repair(line);
+        let fixed = |edits, fixed: &str| Repair {
+            edits,
+            fixed: String::from(fixed),
+        };
+
+        assert_eq!(repair("([])"), fixed(0, "([])"));
+        assert_eq!(repair("([)"), fixed(1, "([])"));
+        assert_eq!(repair("(]"), fixed(1, "()"));
+        assert_eq!(repair("(("), fixed(1, "()"));
+        assert_eq!(repair("(x)"), fixed(1, "()"));
+        assert_eq!(repair("])"), fixed(1, "()"));
+        assert_eq!(repair("]"), fixed(1, "[]"));
+        assert_eq!(repair("(]())"), fixed(1, "([]())"));
+        assert_eq!(repair(""), fixed(0, ""));
+
+        for line in ["[(]", "(()[)]]", "x(x", "))((", "[[)(]]"] {
+            let repair = repair(line);
+            assert_eq!(delimiters.check(&repair.fixed), Ok(()), "{}", line);
+        }
+
+        // every opener is closed right away, a chain as long as the line
+        let line = "(".repeat(200);
+        let repair = repair(&line);
+        assert_eq!(repair.edits, 100);
+        assert_eq!(repair.fixed, "()".repeat(100));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::flatmap::{Backend, FlatMap, Hashed, Linear};
+use std::fmt::Debug;
+
+/// Counts how often each key occurs. Keys are kept in the order they were
+/// first counted, which is also how ties between equal counts are broken.
+#[derive(Clone)]
+pub struct Counter<K: Clone + PartialEq, B: Backend<K> = Linear> {
+    counts: FlatMap<K, usize, B>,
+}
+
+pub type HashCounter<K> = Counter<K, Hashed<K>>;
+
+impl<K: Clone + PartialEq, B: Backend<K>> Counter<K, B> {
+    pub fn new() -> Self {
+        Self {
+            counts: FlatMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, key: &K, n: usize) {
+        *self.counts.at(key, &0) += n;
+    }
+
+    /// Lowers the count of `key` by `n`, dropping it once it reaches zero.
+    pub fn subtract(&mut self, key: &K, n: usize) {
+        if let Some(count) = self.counts.get_mut(key) {
+            if *count > n {
+                *count -= n;
+            } else {
+                self.counts.remove(key);
+            }
+        }
+    }
+
+    pub fn count(&self, key: &K) -> usize {
+        self.counts.get(key).copied().unwrap_or(0)
+    }
+
+    /// Number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Sum of all counts.
+    pub fn total(&self) -> usize {
+        self.counts.iter().map(|v| v.1).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, usize)> + '_ {
+        self.counts.iter().map(|v| (&v.0, v.1))
+    }
+
+    /// The `n` most common keys, most common first.
+    pub fn most_common(&self, n: usize) -> Vec<(K, usize)> {
+        let mut ret = self.counts.data().clone();
+        ret.sort_by_key(|v| std::cmp::Reverse(v.1));
+        ret.truncate(n);
+        ret
+    }
+
+    /// The key with the highest count, the earliest one if several are tied.
+    pub fn max(&self) -> Option<(&K, usize)> {
+        self.iter().fold(None, |best, v| match best {
+            Some(best) if best.1 >= v.1 => Some(best),
+            _ => Some(v),
+        })
+    }
+
+    /// The key with the lowest count, the earliest one if several are tied.
+    pub fn min(&self) -> Option<(&K, usize)> {
+        self.iter().fold(None, |best, v| match best {
+            Some(best) if best.1 <= v.1 => Some(best),
+            _ => Some(v),
+        })
+    }
+
+    /// Adds all counts of `other` to this counter.
+    pub fn merge<C: Backend<K>>(&mut self, other: &Counter<K, C>) {
+        for (key, n) in other.iter() {
+            self.add(key, n);
+        }
+    }
+}
+
+impl<K: Clone + PartialEq, B: Backend<K>> Default for Counter<K, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone + PartialEq, B: Backend<K>> Extend<K> for Counter<K, B> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.add(&key, 1);
+        }
+    }
+}
+
+impl<K: Clone + PartialEq, B: Backend<K>> FromIterator<K> for Counter<K, B> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut ret = Self::new();
+        ret.extend(iter);
+        ret
+    }
+}
+
+impl<K: Clone + PartialEq + Debug, B: Backend<K>> Debug for Counter<K, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.counts.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Counter, HashCounter};
+
+    #[test]
+    fn test_counts() {
+        let mut counter: Counter<char> = "mississippi".chars().collect();
+        assert_eq!(counter.count(&'s'), 4);
+        assert_eq!(counter.count(&'x'), 0);
+        assert_eq!(counter.total(), 11);
+        assert_eq!(counter.len(), 4);
+        assert_eq!(counter.most_common(2), vec![('i', 4), ('s', 4)]);
+        assert_eq!(counter.max(), Some((&'i', 4)));
+        assert_eq!(counter.min(), Some((&'m', 1)));
+
+        counter.subtract(&'m', 5);
+        counter.subtract(&'i', 1);
+        assert_eq!(counter.len(), 3);
+        assert_eq!(counter.max(), Some((&'s', 4)));
+        assert_eq!(counter.min(), Some((&'p', 2)));
+
+        let other: HashCounter<char> = "spam".chars().collect();
+        counter.merge(&other);
+        assert_eq!(counter.count(&'s'), 5);
+        assert_eq!(counter.count(&'m'), 1);
+        assert_eq!(counter.total(), 13);
+    }
+}
@@ -1,96 +1,107 @@
-fn opens(c: char) -> Option<char> {
-    match c {
-        '[' => Some(']'),
-        '(' => Some(')'),
-        '{' => Some('}'),
-        '<' => Some('>'),
-        _ => None,
-    }
+use crate::brackets::{DelimiterError, Delimiters, Pair, Recovery};
+use crate::export;
+
+/// The bracket pairs of the navigation subsystem, with their syntax error
+/// and autocomplete scores.
+fn navigation() -> Delimiters {
+    let pair = |open, close, corrupted, completion| Pair {
+        open,
+        close,
+        corrupted,
+        completion,
+    };
+    Delimiters::new(
+        &[
+            pair('(', ')', 3, 1),
+            pair('[', ']', 57, 2),
+            pair('{', '}', 1197, 3),
+            pair('<', '>', 25137, 4),
+        ],
+        5,
+    )
 }
 
-fn illegal_value(c: char) -> usize {
-    match c {
-        ')' => 3,
-        ']' => 57,
-        '}' => 1197,
-        '>' => 25137,
-        _ => panic!("invalid char"),
-    }
-}
+/// Total syntax error score of corrupted lines and the middle autocomplete
+/// score of incomplete ones.
+fn scores<'a, I: Iterator<Item = &'a str>>(lines: I) -> (usize, usize) {
+    let delimiters = navigation();
 
-fn complete_value(c: char) -> usize {
-    match c {
-        ')' => 1,
-        ']' => 2,
-        '}' => 3,
-        '>' => 4,
-        _ => panic!("invalid char"),
+    let mut task1 = 0;
+    let mut task2 = Vec::new();
+    for line in lines {
+        match delimiters.check(line) {
+            Err(DelimiterError::Corrupted { found, .. }) => {
+                task1 += delimiters.corrupted_score(found);
+            }
+            Err(DelimiterError::Incomplete { completion }) => {
+                task2.push(delimiters.completion_score(&completion));
+            }
+            Ok(()) => {}
+        }
     }
-}
 
-struct Parser {
-    line: String,
+    task2.sort();
+    (task1, task2[task2.len() / 2])
 }
 
-impl Parser {
-    fn new(line: &str) -> Self {
-        let line = String::from(line);
-        Self { line }
-    }
-
-    // returns either the next char to be parsed, or the error char
-    fn parse(&self, mut pos: usize, stack: &mut Vec<char>) -> Result<usize, char> {
-        while pos < self.line.len() {
-            let next = self.line.chars().nth(pos).unwrap();
-
-            // found closing character?
-            if let Some(closing) = stack.last() {
-                if next == *closing {
-                    stack.pop();
-                    // println!("{}: closing = {}", pos, next);
-                    return Ok(pos + 1);
+/// Every error in each corrupted line, carrying on by substituting the
+/// expected closer, followed by a minimal repair of the line.
+fn report<'a, I: Iterator<Item = &'a str>>(lines: I) -> String {
+    let delimiters = navigation();
+
+    let mut ret = String::new();
+    for (number, line) in lines.enumerate() {
+        if !matches!(
+            delimiters.check(line),
+            Err(DelimiterError::Corrupted { .. })
+        ) {
+            continue;
+        }
+        ret.push_str(&format!("line {}: {}\n", number + 1, line));
+        for error in delimiters.check_all(line, Recovery::Substitute) {
+            match error {
+                DelimiterError::Corrupted {
+                    offset,
+                    expected: Some(expected),
+                    found,
+                } => ret.push_str(&format!(
+                    "  offset {}: expected {}, found {}\n",
+                    offset, expected, found
+                )),
+                DelimiterError::Corrupted {
+                    offset,
+                    expected: None,
+                    found,
+                } => ret.push_str(&format!(
+                    "  offset {}: nothing open, found {}\n",
+                    offset, found
+                )),
+                DelimiterError::Incomplete { completion } => {
+                    ret.push_str(&format!("  incomplete, missing {}\n", completion))
                 }
             }
-
-            let open = opens(next);
-
-            // found an invalid closing character?
-            if open.is_none() {
-                // println!("{}: invalid = {}", pos, next);
-                return Err(next);
-            }
-
-            // found an opening character -> recurse
-            // println!("{}: opening = {}", pos, next);
-            stack.push(open.unwrap());
-            pos = self.parse(pos + 1, stack)?;
         }
-
-        Ok(self.line.len())
+        let repair = delimiters.repair(line);
+        ret.push_str(&format!("  {} edits: {}\n", repair.edits, repair.fixed));
     }
+    ret
+}
 
-    fn corrupted(&self) -> Option<usize> {
-        let mut stack = Vec::new();
-        let ret = self.parse(0, &mut stack);
-        ret.err().map(|c| illegal_value(c))
-    }
+pub fn solve() {
+    let input = include_str!("inputs/10.txt");
+    let (task1, task2) = scores(input.lines());
+    export::write("day10.txt", || report(input.lines()));
 
-    fn incomplete(&self) -> Option<usize> {
-        let mut stack = Vec::new();
-        let ret = self.parse(0, &mut stack);
-        ret.ok().map(|_| {
-            let mut score = 0;
-            for c in stack.iter().rev() {
-                score *= 5;
-                score += complete_value(*c);
-            }
-            score
-        })
-    }
+    println!("[day 10] task 1 = {}", task1);
+    println!("[day 10] task 2 = {}", task2);
 }
 
-pub fn solve() {
-    let lines = [
+#[cfg(test)]
+mod tests {
+    use super::{navigation, report, scores};
+    use crate::brackets::{DelimiterError, Recovery};
+
+    const EXAMPLE: [&str; 10] = [
         "[({(<(())[]>[[{[]{<()<>>",
         "[(()[<>])]({[<{<<[]>>(",
         "{([(<{}[<>[]}>{[]{[(<()>",
@@ -102,21 +113,65 @@ pub fn solve() {
         "<{([([[(<>()){}]>(<<{{",
         "<{([{{}}[<[[[<>{}]]]>[]]",
     ];
-    let lines = include_str!("inputs/10.txt").split('\n');
 
-    let mut task1 = 0;
-    let mut task2 = Vec::new();
-    for line in lines {
-        if let Some(v) = Parser::new(line).corrupted() {
-            task1 += v;
-        }
-        if let Some(score) = Parser::new(line).incomplete() {
-            task2.push(score);
-        }
+    #[test]
+    fn test_example() {
+        assert_eq!(scores(EXAMPLE.into_iter()), (26397, 288957));
+
+        let delimiters = navigation();
+        assert_eq!(
+            delimiters.check(EXAMPLE[2]),
+            Err(DelimiterError::Corrupted {
+                offset: 12,
+                expected: Some(']'),
+                found: '}'
+            })
+        );
+        assert_eq!(
+            delimiters.check(EXAMPLE[0]),
+            Err(DelimiterError::Incomplete {
+                completion: String::from("}}]])})]")
+            })
+        );
     }
 
-    task2.sort();
+    #[test]
+    fn test_recovery() {
+        let delimiters = navigation();
+        for line in EXAMPLE {
+            let first = delimiters.check(line).err().unwrap();
+            for recovery in [Recovery::Delete, Recovery::Substitute] {
+                let all = delimiters.check_all(line, recovery);
+                assert_eq!(all[0], first);
+            }
 
-    println!("[day 10] task 1 = {}", task1);
-    println!("[day 10] task 2 = {}", task2[task2.len() / 2]);
+            let repair = delimiters.repair(line);
+            assert_eq!(delimiters.check(&repair.fixed), Ok(()));
+        }
+
+        // substituting "]" for the first "}" leaves the line incomplete,
+        // while a minimal repair changes the line more cleverly
+        let all = delimiters.check_all(EXAMPLE[2], Recovery::Substitute);
+        assert_eq!(
+            all[1],
+            DelimiterError::Incomplete {
+                completion: String::from(")]}})])}")
+            }
+        );
+        let repair = delimiters.repair(EXAMPLE[2]);
+        assert_eq!(repair.edits, 5);
+        assert_eq!(repair.fixed, "{}[]<{}[<>[]]>{[]}[]<()>");
+    }
+
+    #[test]
+    fn test_report() {
+        let report = report(EXAMPLE[2..4].iter().copied());
+        assert_eq!(
+            report,
+            "line 1: {([(<{}[<>[]}>{[]{[(<()>\n\
+             \x20 offset 12: expected ], found }\n\
+             \x20 incomplete, missing )]}})])}\n\
+             \x20 5 edits: {}[]<{}[<>[]]>{[]}[]<()>\n"
+        );
+    }
 }
@@ -1,82 +1,54 @@
+use crate::array2d::Array2D;
+use crate::export;
+use crate::recorder::Recorder;
+
 struct Data {
-    v: [[i32; 10]; 10],
+    v: Array2D<i32>,
 }
 
 impl Data {
     fn new(lines: &str) -> Self {
-        let lines: Vec<&str> = lines.split('\n').filter(|l| !l.is_empty()).collect();
-        assert!(lines.len() == 10);
-
-        let mut v = [[0; 10]; 10];
-        for y in 0..10 {
-            assert!(lines[y].len() == 10);
-            for x in 0..10 {
-                let value = lines[y].chars().nth(x).unwrap();
-                let value = value as i32 - '0' as i32;
-                assert!(value >= 0 && value < 10);
-                v[y][x] = value;
-            }
-        }
-
+        let v = Array2D::parse_grid(lines, |c| c.to_digit(10).map(|d| d as i32).ok_or(c))
+            .expect("invalid input");
         Self { v }
     }
 
+    fn size(&self) -> usize {
+        self.v.width() * self.v.height()
+    }
+
     fn print(&self) {
-        for i in 0..10 {
-            println!(
-                "{}{}{}{}{}{}{}{}{}{}",
-                self.v[i][0],
-                self.v[i][1],
-                self.v[i][2],
-                self.v[i][3],
-                self.v[i][4],
-                self.v[i][5],
-                self.v[i][6],
-                self.v[i][7],
-                self.v[i][8],
-                self.v[i][9],
-            )
-        }
+        print!(
+            "{}",
+            self.v
+                .to_grid_string(|v| char::from_digit(*v as u32, 10).unwrap_or('*'))
+        );
     }
 
     fn step(&mut self) -> usize {
         // increase everything by 1
-        for y in 0..10 {
-            for x in 0..10 {
-                self.v[y][x] += 1;
-            }
+        for v in self.v.iter_mut() {
+            *v += 1;
         }
 
         let mut ret = 0;
-        let mut flashed = [[false; 10]; 10];
+        let mut flashed = Array2D::new(self.v.width(), self.v.height());
+        flashed.fill(false);
 
         // flash
         loop {
             let mut v2 = self.v.clone();
             let mut flashed_now = 0;
 
-            for y in 0..10 {
-                for x in 0..10 {
-                    if !flashed[y][x] && self.v[y][x] > 9 {
-                        flashed[y][x] = true;
+            for y in 0..self.v.height() {
+                for x in 0..self.v.width() {
+                    if !flashed.at((x, y)) && *self.v.at((x, y)) > 9 {
+                        flashed.set((x, y), true);
                         flashed_now += 1;
 
                         // increase neighbors
-                        for dx in -1..2 {
-                            for dy in -1..2 {
-                                let x2 = dx + x as i32;
-                                let y2 = dy + y as i32;
-                                if dx == 0 && dy == 0 {
-                                    continue;
-                                }
-                                if x2 < 0 || x2 >= 10 {
-                                    continue;
-                                }
-                                if y2 < 0 || y2 >= 10 {
-                                    continue;
-                                }
-                                v2[y2 as usize][x2 as usize] += 1;
-                            }
+                        for n in self.v.neighbors8((x, y)) {
+                            *v2.at_mut(n) += 1;
                         }
                     }
                 }
@@ -91,11 +63,9 @@ impl Data {
         }
 
         // reset to 0 if flashed
-        for y in 0..10 {
-            for x in 0..10 {
-                if flashed[y][x] {
-                    self.v[y][x] = 0;
-                }
+        for (v, flashed) in self.v.iter_mut().zip(flashed.iter()) {
+            if *flashed {
+                *v = 0;
             }
         }
 
@@ -105,6 +75,13 @@ impl Data {
 
 pub fn solve() {
     let mut data = Data::new(include_str!("inputs/11.txt"));
+    export::write("day11.pgm", || data.v.to_pgm(16, |v| *v as u8 * 25));
+    // the initial state and the hundred steps of task 1
+    let mut recorder = export::dir()
+        .map(|_| Recorder::new(|v: &Array2D<i32>| v.to_pgm(8, |v| *v as u8 * 25)).steps(0..101));
+    if let Some(recorder) = &mut recorder {
+        recorder.capture(0, &data.v);
+    }
 
     let mut task1 = 0;
     let mut task2 = None;
@@ -115,11 +92,18 @@ pub fn solve() {
             task1 += step;
         }
         count += 1;
+        if let Some(recorder) = &mut recorder {
+            recorder.capture(count, &data.v);
+        }
 
-        if step == 100 {
+        if step == data.size() {
             task2 = Some(count);
         }
     }
+    if let Some(recorder) = recorder {
+        recorder.export("day11_", "pgm");
+    }
+
     println!("[day 11] task 1 = {}", task1);
     println!("[day 11] task 2 = {}", task2.unwrap());
 }
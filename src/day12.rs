@@ -1,3 +1,8 @@
+use crate::counter::HashCounter;
+use crate::export;
+use crate::flatmap::FlatMap;
+use crate::graph::{Graph, OneSmallTwice, PathStats, SmallAtMost};
+
 #[derive(PartialEq, Eq, Clone, Copy)]
 enum CaveType {
     Small,
@@ -7,153 +12,328 @@ enum CaveType {
 }
 
 impl CaveType {
-    fn from(id: &str) -> CaveType {
+    /// Returns `None` for names that are neither all lowercase nor all uppercase.
+    fn from(id: &str) -> Option<CaveType> {
         if id == "start" {
-            return CaveType::Start;
-        }
-        if id == "end" {
-            return CaveType::End;
+            Some(CaveType::Start)
+        } else if id == "end" {
+            Some(CaveType::End)
+        } else if id.chars().all(|c| c.is_lowercase()) {
+            Some(CaveType::Small)
+        } else if id.chars().all(|c| c.is_uppercase()) {
+            Some(CaveType::Big)
+        } else {
+            None
         }
-        if id.chars().all(|c| c.is_lowercase()) {
-            return CaveType::Small;
-        }
-        assert!(id.chars().all(|c| c.is_uppercase()));
-        return CaveType::Big;
     }
 }
 
+/// Problems found in a cave system description.
+/// Lines are 0-based and refer to the input text.
+#[derive(Debug, PartialEq)]
+enum CaveError {
+    /// Not of the form `name-name`.
+    Malformed {
+        line: usize,
+    },
+    MixedCase {
+        line: usize,
+        name: String,
+    },
+    /// The same pair of caves was already connected on line `first`.
+    DuplicateEdge {
+        line: usize,
+        first: usize,
+    },
+    MissingCave {
+        name: &'static str,
+    },
+    /// Two connected big caves allow infinitely many paths.
+    AdjacentBigCaves {
+        line: usize,
+    },
+}
+
 struct Cave {
     id: String,
-    index: usize,
     ty: CaveType,
-    edges: Vec<usize>,
+}
+
+impl Cave {
+    fn is_small(&self) -> bool {
+        self.ty == CaveType::Small
+    }
 }
 
 struct Data {
-    caves: Vec<Cave>,
+    caves: Graph<Cave>,
 }
 
 impl Data {
-    fn new(data: &str) -> Self {
-        let mut ids = Vec::new();
-
-        // collect IDs
-        for line in data.split('\n').filter(|s| !s.is_empty()) {
-            let mut parts = line.split('-');
-            let cave1 = parts.next().expect("invalid input");
-            let cave2 = parts.next().expect("invalid input");
+    /// Parses one connection per line, collecting all problems with the input.
+    fn new(data: &str) -> Result<Self, Vec<CaveError>> {
+        let mut caves = Graph::new();
+        // line each connection was first given on
+        let mut edge_lines: FlatMap<(usize, usize), usize> = FlatMap::new();
+        let mut errors = Vec::new();
 
-            if !ids.contains(&cave1) {
-                ids.push(&cave1);
-            }
-            if !ids.contains(&cave2) {
-                ids.push(&cave2);
+        for (line, s) in data.lines().enumerate().filter(|(_, s)| !s.is_empty()) {
+            let ids: Vec<&str> = s.split('-').collect();
+            if ids.len() != 2 || ids.iter().any(|id| id.is_empty()) {
+                errors.push(CaveError::Malformed { line });
+                continue;
             }
-        }
 
-        // create caves
-        let mut caves: Vec<Cave> = ids
-            .iter()
-            .enumerate()
-            .map(|(index, id)| {
-                let ty = CaveType::from(id);
-                let id = String::from(*id);
-                let edges = Vec::new();
-                Cave {
-                    id,
-                    index,
-                    ty,
-                    edges,
+            let mut index = |id: &str| {
+                if let Some(idx) = caves.find(|c: &Cave| c.id == id) {
+                    return Some(idx);
                 }
-            })
-            .collect();
-
-        // gather edges
-        for line in data.split('\n').filter(|s| !s.is_empty()) {
-            let mut parts = line.split('-');
-            let cave1 = parts.next().expect("invalid input");
-            let cave2 = parts.next().expect("invalid input");
+                let Some(ty) = CaveType::from(id) else {
+                    let name = String::from(id);
+                    errors.push(CaveError::MixedCase { line, name });
+                    return None;
+                };
+                let id = String::from(id);
+                Some(caves.add_node(Cave { id, ty }))
+            };
+            let (Some(cave1), Some(cave2)) = (index(ids[0]), index(ids[1])) else {
+                continue;
+            };
 
-            let cave1 = ids.iter().position(|c| *c == cave1).expect("invalid input");
-            let cave2 = ids.iter().position(|c| *c == cave2).expect("invalid input");
+            let edge = (cave1.min(cave2), cave1.max(cave2));
+            if let Some(&first) = edge_lines.get(&edge) {
+                errors.push(CaveError::DuplicateEdge { line, first });
+                continue;
+            }
+            let big = |idx| caves.node(idx).label.ty == CaveType::Big;
+            if big(cave1) && big(cave2) {
+                errors.push(CaveError::AdjacentBigCaves { line });
+            }
 
-            assert!(!caves[cave1].edges.contains(&cave2));
-            assert!(!caves[cave2].edges.contains(&cave1));
+            edge_lines.set(&edge, line);
+            caves.add_edge(cave1, cave2);
+        }
 
-            caves[cave1].edges.push(cave2);
-            caves[cave2].edges.push(cave1);
+        for (ty, name) in [(CaveType::Start, "start"), (CaveType::End, "end")] {
+            if caves.find(|c| c.ty == ty).is_none() {
+                errors.push(CaveError::MissingCave { name });
+            }
         }
 
-        Self { caves }
+        if errors.is_empty() {
+            Ok(Self { caves })
+        } else {
+            Err(errors)
+        }
     }
 
-    fn traverse(
-        &self,
-        curr_path: &mut Vec<usize>,
-        visited_count: &mut Vec<usize>,
-        results: &mut (usize, usize),
-        mut used_extra_small_cave: bool,
-    ) {
-        let curr_cave = &self.caves[*curr_path.last().unwrap()];
-        results.1 += 1;
-
-        // if we arrived at the end, make note and continue
-        if curr_cave.ty == CaveType::End {
-            results.0 += 1;
-            return;
-        }
+    fn cave(&self, ty: CaveType) -> usize {
+        self.caves
+            .find(|c| c.ty == ty)
+            .expect("validated in Data::new")
+    }
 
-        for edge in &curr_cave.edges {
-            let edge_cave = &self.caves[*edge];
-            let edge_visisted = visited_count[*edge];
-            let mut used_extra_small_cave = used_extra_small_cave;
-
-            let visit = match edge_cave.ty {
-                CaveType::Start => false,
-                CaveType::End => true,
-                CaveType::Small => {
-                    if edge_visisted < 1 {
-                        true
-                    } else if edge_visisted == 1 && !used_extra_small_cave {
-                        used_extra_small_cave = true;
-                        true
-                    } else {
-                        false
-                    }
-                }
-                CaveType::Big => true,
+    /// Calls `f` with all paths from start to end, optionally allowing one
+    /// small cave to be visited twice.
+    fn for_each_path<F: FnMut(&[usize])>(&self, extra_cave: bool, f: F) {
+        let (start, end) = (self.cave(CaveType::Start), self.cave(CaveType::End));
+        if extra_cave {
+            let policy = OneSmallTwice {
+                small: Cave::is_small,
+                required: false,
             };
-            if visit {
-                visited_count[*edge] += 1;
-                curr_path.push(*edge);
-                self.traverse(curr_path, visited_count, results, used_extra_small_cave);
-                curr_path.pop();
-                visited_count[*edge] -= 1;
-            }
+            self.caves.for_each_path(start, end, &policy, f);
+        } else {
+            let policy = SmallAtMost {
+                small: Cave::is_small,
+                k: 1,
+            };
+            self.caves.for_each_path(start, end, &policy, f);
         }
     }
 
-    fn find_all_paths(&self, extra_cave: bool) -> usize {
-        let start_idx = self
-            .caves
-            .iter()
-            .position(|c| c.ty == CaveType::Start)
-            .expect("missing start cave");
+    fn paths(&self, extra_cave: bool) -> Vec<Vec<usize>> {
+        let mut ret = Vec::new();
+        self.for_each_path(extra_cave, |path| ret.push(path.to_vec()));
+        ret
+    }
 
-        let mut curr_path = vec![start_idx];
-        let mut visited_count = vec![0; self.caves.len()];
-        visited_count[start_idx] = 1;
+    /// How many of all paths use each edge, keyed by the lower cave index first.
+    fn edge_usage(&self, extra_cave: bool) -> HashCounter<(usize, usize)> {
+        let mut ret = HashCounter::new();
+        self.for_each_path(extra_cave, |path| {
+            for step in path.windows(2) {
+                ret.add(&(step[0].min(step[1]), step[0].max(step[1])), 1);
+            }
+        });
+        ret
+    }
 
-        let mut result = (0, 0);
-        self.traverse(&mut curr_path, &mut visited_count, &mut result, !extra_cave);
+    /// Renders the caves as a Graphviz graph, styled by cave type. Edges are
+    /// drawn thicker and redder the more paths in `usage` take them, and the
+    /// caves and edges along `path` are drawn in blue.
+    fn to_dot(
+        &self,
+        path: Option<&[usize]>,
+        usage: Option<&HashCounter<(usize, usize)>>,
+    ) -> String {
+        let path = path.unwrap_or(&[]);
+        let on_path = |a: usize, b: usize| {
+            path.windows(2)
+                .any(|step| (step[0], step[1]) == (a, b) || (step[0], step[1]) == (b, a))
+        };
+        let max_usage = usage.and_then(|u| u.max()).map_or(1, |v| v.1.max(1));
 
-        result.0
+        self.caves.to_dot(
+            |idx, cave| {
+                let style = match cave.ty {
+                    CaveType::Start => "shape=box, style=filled, fillcolor=palegreen",
+                    CaveType::End => "shape=box, style=filled, fillcolor=lightpink",
+                    CaveType::Small => "shape=ellipse",
+                    CaveType::Big => "shape=doublecircle, style=bold",
+                };
+                let color = if path.contains(&idx) {
+                    ", color=blue"
+                } else {
+                    ""
+                };
+                format!("label=\"{}\", {}{}", cave.id, style, color)
+            },
+            |a, b| {
+                let mut attrs = Vec::new();
+                let count = usage.map(|u| u.count(&(a, b)));
+                if let Some(count) = count {
+                    attrs.push(format!("label=\"{}\"", count));
+                }
+                // the path style wins over the heat style
+                if on_path(a, b) {
+                    attrs.push(String::from("color=blue, penwidth=3"));
+                } else if let Some(count) = count {
+                    let heat = count * 255 / max_usage;
+                    attrs.push(format!("color=\"#{:02x}0000\"", heat));
+                    attrs.push(format!("penwidth={}", 1 + heat / 51));
+                }
+                attrs.join(", ")
+            },
+        )
+    }
+
+    fn find_all_paths(&self, extra_cave: bool) -> PathStats {
+        let (start, end) = (self.cave(CaveType::Start), self.cave(CaveType::End));
+        self.caves
+            .count_paths_memoized(start, end, Cave::is_small, extra_cave)
     }
 }
 
 pub fn solve() {
-    let data = Data::new(include_str!("inputs/12.txt"));
+    let data = Data::new(include_str!("inputs/12.txt")).unwrap();
+
+    println!("[day 12] task 1 = {}", data.find_all_paths(false).paths);
+    println!("[day 12] task 2 = {}", data.find_all_paths(true).paths);
+
+    export::write("day12.dot", || {
+        data.to_dot(None, Some(&data.edge_usage(true)))
+    });
+    export::write("day12_path.dot", || {
+        let longest = data.paths(false).into_iter().max_by_key(|p| p.len());
+        data.to_dot(longest.as_deref(), None)
+    });
+}
 
-    println!("[day 12] task 1 = {}", data.find_all_paths(false));
-    println!("[day 12] task 2 = {}", data.find_all_paths(true));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        let examples = [
+            (include_str!("inputs/12_a.txt"), 10, 36),
+            (include_str!("inputs/12_b.txt"), 19, 103),
+            (include_str!("inputs/12_c.txt"), 226, 3509),
+        ];
+        for (input, once, twice) in examples {
+            let data = Data::new(input).unwrap();
+            assert_eq!(data.find_all_paths(false).paths, once);
+            assert_eq!(data.find_all_paths(true).paths, twice);
+            assert_eq!(data.paths(false).len(), once);
+            assert_eq!(data.paths(true).len(), twice);
+        }
+
+        // the same number of steps the explicit traversal takes
+        let data = Data::new(examples[2].0).unwrap();
+        assert_eq!(data.find_all_paths(false).visited, 1233);
+        assert_eq!(data.find_all_paths(true).visited, 20920);
+
+        let data = Data::new(examples[0].0).unwrap();
+        let names = |path: &Vec<usize>| -> Vec<&str> {
+            path.iter()
+                .map(|c| data.caves.node(*c).label.id.as_str())
+                .collect()
+        };
+        let paths = data.paths(false);
+        assert_eq!(paths.len(), 10);
+
+        // every path leaves the start cave exactly once
+        let usage = data.edge_usage(false);
+        let start = data.cave(CaveType::Start);
+        let from_start: usize = usage.iter().filter(|e| e.0 .0 == start).map(|e| e.1).sum();
+        assert_eq!(from_start, 10);
+
+        let dot = data.to_dot(Some(&paths[0]), Some(&usage));
+        assert!(dot.starts_with("graph {\n    0 [label=\"start\", shape=box"));
+        assert_eq!(dot.matches(" -- ").count(), 7);
+        let mut steps: Vec<_> = paths[0]
+            .windows(2)
+            .map(|s| (s[0].min(s[1]), s[0].max(s[1])))
+            .collect();
+        steps.sort();
+        steps.dedup();
+        assert_eq!(dot.matches("color=blue, penwidth=3").count(), steps.len());
+        assert_eq!(dot.matches("penwidth=").count(), 7);
+        assert!(paths
+            .iter()
+            .any(|p| names(p) == ["start", "A", "b", "A", "c", "A", "end"]));
+    }
+
+    #[test]
+    fn test_validation() {
+        let errors = |input| Data::new(input).err().unwrap();
+
+        assert_eq!(
+            errors("start-A\nA-end\nA\nb-c-d\nA-\n"),
+            vec![
+                CaveError::Malformed { line: 2 },
+                CaveError::Malformed { line: 3 },
+                CaveError::Malformed { line: 4 },
+            ]
+        );
+        assert_eq!(
+            errors("start-A\nA-end\nA-start\nend-A\n"),
+            vec![
+                CaveError::DuplicateEdge { line: 2, first: 0 },
+                CaveError::DuplicateEdge { line: 3, first: 1 },
+            ]
+        );
+        assert_eq!(
+            errors("start-Ab\nb-end\n"),
+            vec![CaveError::MixedCase {
+                line: 0,
+                name: String::from("Ab")
+            }]
+        );
+        assert_eq!(
+            errors("start-A\nA-B\nB-b\n"),
+            vec![
+                CaveError::AdjacentBigCaves { line: 1 },
+                CaveError::MissingCave { name: "end" },
+            ]
+        );
+        assert_eq!(
+            errors(""),
+            vec![
+                CaveError::MissingCave { name: "start" },
+                CaveError::MissingCave { name: "end" },
+            ]
+        );
+    }
 }
@@ -1,4 +1,8 @@
 use super::array2d::Array2D;
+use super::bitgrid::BitGrid;
+use super::export;
+use super::grid::{prefer_dense, Grid};
+use super::sparsegrid::SparseGrid;
 
 #[derive(Copy, Clone, Debug)]
 enum Fold {
@@ -6,7 +10,7 @@ enum Fold {
     Vert(usize),
 }
 
-fn parse(input: &str) -> (Array2D<bool>, Vec<Fold>) {
+fn parse(input: &str) -> (Vec<(usize, usize)>, Vec<Fold>) {
     let mut pts = Vec::new();
     let mut folds = Vec::new();
 
@@ -32,94 +36,143 @@ fn parse(input: &str) -> (Array2D<bool>, Vec<Fold>) {
         }
     }
 
-    let width = 1 + pts.iter().map(|xy| xy.0).max().unwrap();
-    let height = 1 + pts.iter().map(|xy| xy.1).max().unwrap();
+    (pts, folds)
+}
 
-    let mut array = Array2D::new(width, height);
+fn paper<G: Grid<bool>>(pts: &[(usize, usize)], width: usize, height: usize) -> G {
+    let mut grid = G::new(width, height);
     for pt in pts {
-        *array.at_mut(pt) = true;
+        grid.set(*pt, true);
     }
-
-    (array, folds)
+    grid
 }
 
-fn fold_vert(data: &Array2D<bool>, fold: usize) -> Array2D<bool> {
+fn fold_vert(data: &BitGrid, fold: usize) -> BitGrid {
     let width = data.width();
     let height = data.height();
 
     assert!(fold + 1 < height);
-    for x in 0..width {
-        assert!(!*data.at((x, fold)));
-    }
+    assert!(2 * fold + 1 >= height);
+    assert!(data.row_words(fold).iter().all(|w| *w == 0));
 
-    let mut new = Array2D::new(width, fold);
-    for y in 0..fold {
-        for x in 0..width {
-            *new.at_mut((x, y)) = *data.at((x, y));
-        }
-    }
-
-    for y in fold + 1..height {
-        let y2 = 2 * fold - y;
-        for x in 0..width {
-            if *data.at((x, y)) {
-                *new.at_mut((x, y2)) = true;
-            }
-        }
-    }
+    let mut new = data.cropped((0, 0), width, fold);
+    let folded = data
+        .cropped((0, fold + 1), width, height - fold - 1)
+        .flipped_vertical();
+    new.merge(&folded, (0, fold - folded.height()), |a, b| a | b);
 
     new
 }
 
-fn fold_hor(data: &Array2D<bool>, fold: usize) -> Array2D<bool> {
+fn fold_hor(data: &BitGrid, fold: usize) -> BitGrid {
     let width = data.width();
     let height = data.height();
 
     assert!(fold + 1 < width);
-    for y in 0..height {
-        assert!(!*data.at((fold, y)));
-    }
+    assert!(2 * fold + 1 >= width);
+    assert!((0..height).all(|y| !data.at((fold, y))));
 
-    let mut new = Array2D::new(fold, height);
-    for y in 0..height {
-        for x in 0..fold {
-            *new.at_mut((x, y)) = *data.at((x, y));
-        }
+    let mut new = data.cropped((0, 0), fold, height);
+    let folded = data
+        .cropped((fold + 1, 0), width - fold - 1, height)
+        .flipped_horizontal();
+    new.merge(&folded, (fold - folded.width(), 0), |a, b| a | b);
+
+    new
+}
+
+fn fold_dense(data: &BitGrid, fold: Fold) -> BitGrid {
+    match fold {
+        Fold::Hor(x) => fold_hor(data, x),
+        Fold::Vert(y) => fold_vert(data, y),
     }
+}
 
-    for y in 0..height {
-        for x in fold + 1..width {
-            let x2 = 2 * fold - x;
-            if *data.at((x, y)) {
-                *new.at_mut((x2, y)) = true;
+/// Folds by moving each dot individually, for grids too large to fold row-wise.
+fn fold_sparse<G: Grid<bool>>(data: &G, fold: Fold) -> G {
+    let (width, height) = match fold {
+        Fold::Hor(x) => (x, data.height()),
+        Fold::Vert(y) => (data.width(), y),
+    };
+
+    let mut new = G::new(width, height);
+    for ((x, y), _) in data.occupied() {
+        let pos = match fold {
+            Fold::Hor(f) => {
+                assert!(x != f && x <= 2 * f);
+                (if x < f { x } else { 2 * f - x }, y)
             }
-        }
+            Fold::Vert(f) => {
+                assert!(y != f && y <= 2 * f);
+                (x, if y < f { y } else { 2 * f - y })
+            }
+        };
+        new.set(pos, true);
     }
 
     new
 }
 
-pub fn solve() {
-    let (data, folds) = parse(include_str!("inputs/13.txt"));
-
-    let mut data = match folds[0] {
-        Fold::Hor(x) => fold_hor(&data, x),
-        Fold::Vert(y) => fold_vert(&data, y),
-    };
-    let task1 = data.iter().filter(|b| **b).count();
+fn run<G: Grid<bool>>(data: G, folds: &[Fold], fold: impl Fn(&G, Fold) -> G) {
+    let mut data = fold(&data, folds[0]);
+    let task1 = data.occupied().count();
     println!("[day 13] task 1 = {}", task1);
 
     for f in &folds[1..] {
-        data = match f {
-            Fold::Hor(x) => fold_hor(&data, *x),
-            Fold::Vert(y) => fold_vert(&data, *y),
-        };
+        data = fold(&data, *f);
     }
 
-    for y in 0..data.height() {
-        let s: String = (0..data.width())
-            .map(|x| if *data.at((x, y)) { "##" } else { "  " })
-            .collect();
+    export::write("day13.pbm", || {
+        let mut paper = Array2D::new(data.width(), data.height());
+        for (pos, _) in data.occupied() {
+            paper[pos] = true;
+        }
+        paper.to_pbm(8, |b| *b)
+    });
+
+    for line in data.render(|b| if b { '#' } else { ' ' }).lines() {
+        let s: String = line.chars().flat_map(|c| [c, c]).collect();
         println!("[day 13] task 2 = {}", s);
     }
 }
+
+pub fn solve() {
+    let (pts, folds) = parse(include_str!("inputs/13.txt"));
+
+    let width = 1 + pts.iter().map(|xy| xy.0).max().unwrap();
+    let height = 1 + pts.iter().map(|xy| xy.1).max().unwrap();
+
+    if prefer_dense(width, height) {
+        run(paper::<BitGrid>(&pts, width, height), &folds, fold_dense);
+    } else {
+        run(
+            paper::<SparseGrid<bool>>(&pts, width, height),
+            &folds,
+            fold_sparse,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dense_and_sparse_folds_agree() {
+        let (pts, folds) = parse(include_str!("inputs/13_a.txt"));
+        let width = 1 + pts.iter().map(|xy| xy.0).max().unwrap();
+        let height = 1 + pts.iter().map(|xy| xy.1).max().unwrap();
+
+        let mut dense = paper::<BitGrid>(&pts, width, height);
+        let mut sparse = paper::<SparseGrid<bool>>(&pts, width, height);
+        for f in &folds {
+            dense = fold_dense(&dense, *f);
+            sparse = fold_sparse(&sparse, *f);
+            assert_eq!(dense.occupied().count(), sparse.occupied().count());
+        }
+
+        let render = |b| if b { '#' } else { '.' };
+        assert_eq!(dense.render(render), sparse.render(render));
+        assert_eq!(dense.occupied().count(), 16);
+    }
+}
@@ -1,9 +1,10 @@
+use super::counter::Counter;
 use super::flatmap::FlatMap;
 
 struct Rules(FlatMap<(char, char), char>);
 
 struct Polymer {
-    pairs: FlatMap<(char, char), usize>,
+    pairs: Counter<(char, char)>,
 }
 
 impl Rules {
@@ -36,48 +37,43 @@ impl Rules {
 
 impl Polymer {
     fn new(s: &str) -> Self {
-        let mut pairs = FlatMap::new();
-        for i in 0..(s.len() - 1) {
-            let a = s.chars().nth(i).unwrap();
-            let b = s.chars().nth(i + 1).unwrap();
-            *pairs.at(&(a, b), &0) += 1;
-        }
+        let pairs = s.chars().zip(s.chars().skip(1)).collect();
         Self { pairs }
     }
 
     fn apply(&self, rules: &Rules) -> Self {
-        let mut new_pairs = FlatMap::new();
-        for pair in self.pairs.iter() {
-            let a = pair.0 .0;
-            let b = pair.0 .1;
+        let mut new_pairs = Counter::new();
+        for (&(a, b), n) in self.pairs.iter() {
             if let Some(c) = rules.insert(a, b) {
-                *new_pairs.at(&(a, c), &0) += pair.1;
-                *new_pairs.at(&(c, b), &0) += pair.1;
+                new_pairs.add(&(a, c), n);
+                new_pairs.add(&(c, b), n);
             } else {
-                *new_pairs.at(&(a, b), &0) += pair.1;
+                new_pairs.add(&(a, b), n);
             }
         }
         Self { pairs: new_pairs }
     }
 
     fn total(&self) -> usize {
-        self.pairs.iter().map(|pair| pair.1).sum()
+        self.pairs.total()
     }
 
     fn score(&self) -> usize {
-        let mut chars = self.chars().data().clone();
-        chars.sort_by(|a, b| a.1.cmp(&b.1));
-        chars.last().unwrap().1 - chars.first().unwrap().1
+        let chars = self.chars();
+        chars.max().unwrap().1 - chars.min().unwrap().1
     }
 
-    fn chars(&self) -> FlatMap<char, usize> {
-        let mut ret = FlatMap::new();
-        for pair in self.pairs.iter() {
-            *ret.at(&pair.0 .0, &0) += pair.1;
-            *ret.at(&pair.0 .1, &0) += pair.1;
+    fn chars(&self) -> Counter<char> {
+        // every char is part of two pairs, except for the very first and last
+        let mut twice: Counter<char> = Counter::new();
+        for (&(a, b), n) in self.pairs.iter() {
+            twice.add(&a, n);
+            twice.add(&b, n);
         }
-        for value in ret.iter_mut() {
-            value.1 = (value.1 + 1) / 2;
+
+        let mut ret = Counter::new();
+        for (c, n) in twice.iter() {
+            ret.add(c, n.div_ceil(2));
         }
         ret
     }
@@ -1,20 +1,9 @@
-use crate::array2d::Array2D;
+use crate::array2d::{Array2D, ParseGridError};
+use crate::bitgrid::BitGrid;
 use std::collections::VecDeque;
 
-fn load(input: &str) -> Array2D<usize> {
-    let lines: Vec<&str> = input.split('\n').filter(|line| !line.is_empty()).collect();
-    let mut ret = Array2D::new(lines.len(), lines[0].len());
-
-    for line in lines.iter().enumerate() {
-        assert!(line.1.len() == ret.width());
-        for x in 0..ret.width() {
-            let value = line.1.chars().nth(x).unwrap();
-            let value = value as usize - '0' as usize;
-            ret.set((x, line.0), value);
-        }
-    }
-
-    ret
+fn load(input: &str) -> Result<Array2D<usize>, ParseGridError<char>> {
+    Array2D::parse_grid(input, |c| c.to_digit(10).map(|d| d as usize).ok_or(c))
 }
 
 fn extend(input: &Array2D<usize>, n: usize) -> Array2D<usize> {
@@ -24,15 +13,9 @@ fn extend(input: &Array2D<usize>, n: usize) -> Array2D<usize> {
     let mut ret = Array2D::new(n * w, n * h);
     for j in 0..n {
         for i in 0..n {
-            for y in 0..h {
-                for x in 0..w {
-                    let mut v = input.at((x, y)) + i + j;
-                    while v > 9 {
-                        v -= 9;
-                    }
-                    ret.set((i * w + x, j * h + y), v);
-                }
-            }
+            ret.merge(input, (i * w, j * h), |v, risk| {
+                *v = (risk + i + j - 1) % 9 + 1;
+            });
         }
     }
 
@@ -43,15 +26,14 @@ struct Solver {
     risks: Array2D<usize>,
     visited: Array2D<usize>,
     to_visit: VecDeque<(usize, usize)>,
-    marked_to_visit: Array2D<bool>,
+    marked_to_visit: BitGrid,
 }
 
 impl Solver {
     fn new(risks: Array2D<usize>) -> Self {
         let mut visited = Array2D::new(risks.width(), risks.height());
-        let mut marked_to_visit = Array2D::new(risks.width(), risks.height());
+        let marked_to_visit = BitGrid::new(risks.width(), risks.height());
         visited.fill(usize::MAX);
-        marked_to_visit.fill(false);
         let to_visit = VecDeque::new();
 
         Self {
@@ -67,19 +49,7 @@ impl Solver {
         assert!(risk < usize::MAX);
 
         // check if, starting from this position, there is a safer way to any of the neighbors
-        for (dx, dy) in &[(-1, 0), (1, 0), (0, -1), (0, 1)] {
-            let x2 = pos.0 as isize + dx;
-            let y2 = pos.1 as isize + dy;
-            if x2 < 0
-                || x2 >= self.visited.width() as isize
-                || y2 < 0
-                || y2 >= self.visited.height() as isize
-            {
-                continue;
-            }
-
-            let pos2 = (x2 as usize, y2 as usize);
-
+        for pos2 in self.risks.neighbors4(pos) {
             let new_risk = risk + *self.risks.at(pos2);
             if new_risk < *self.visited.at(pos2) {
                 self.visited.set(pos2, new_risk);
@@ -108,7 +78,7 @@ impl Solver {
 }
 
 pub fn solve() {
-    let risk1 = load(include_str!("inputs/15.txt"));
+    let risk1 = load(include_str!("inputs/15.txt")).unwrap();
     let risk2 = extend(&risk1, 5);
 
     let task1 = Solver::new(risk1).solve();
@@ -117,3 +87,46 @@ pub fn solve() {
     let task2 = Solver::new(risk2).solve();
     println!("[day 15] task 2 = {}", task2);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{extend, load, ParseGridError, Solver};
+
+    #[test]
+    fn test_load_non_square() {
+        let risks = load("1163\n1381\n").unwrap();
+        assert_eq!(risks.width(), 4);
+        assert_eq!(risks.height(), 2);
+        assert_eq!(*risks.at((3, 0)), 3);
+        assert_eq!(*risks.at((1, 1)), 3);
+
+        let extended = extend(&risks, 2);
+        assert_eq!(extended.width(), 8);
+        assert_eq!(extended.height(), 4);
+        assert_eq!(*extended.at((7, 3)), 3);
+
+        assert_eq!(Solver::new(risks).solve(), 1 + 6 + 3 + 1);
+        assert_eq!(Solver::new(extended).solve(), 28);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert_eq!(load("").unwrap_err(), ParseGridError::Empty);
+        assert_eq!(
+            load("123\n12\n").unwrap_err(),
+            ParseGridError::RaggedLine {
+                line: 1,
+                expected: 3,
+                found: 2
+            }
+        );
+        assert_eq!(
+            load("123\n1x3\n").unwrap_err(),
+            ParseGridError::InvalidCell {
+                line: 1,
+                column: 1,
+                error: 'x'
+            }
+        );
+    }
+}
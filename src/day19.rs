@@ -5,7 +5,7 @@ use std::{
     ops::{Add, Sub},
 };
 
-use crate::flatmap::FlatMap;
+use crate::counter::HashCounter;
 
 /*
 
@@ -181,22 +181,18 @@ impl<'a> Scanner<'a> {
         }
 
         for perm in 0..24 {
-            let mut diffs = FlatMap::new();
+            let mut diffs = HashCounter::new();
 
             for our in &self.beacons[0] {
                 for their in &other.beacons[perm] {
-                    let diff = *their - *our;
-                    *diffs.at(&diff, &0) += 1;
+                    diffs.add(&(*their - *our), 1);
                 }
             }
 
-            let mut diffs = diffs.take_data();
-            diffs.sort_by(|a, b| b.1.cmp(&a.1));
-            assert!(!diffs.is_empty());
+            let (&diff, votes) = diffs.max().unwrap();
 
-            if diffs.first().unwrap().1 >= 12 {
+            if votes >= 12 {
                 // we found a match!
-                let diff = diffs.first().unwrap().0;
                 let entry = (other, perm, diff);
 
                 let mut matched_scanners = self.matched_scanners.borrow_mut();
@@ -1,115 +1,111 @@
 use crate::array2d::Array2D;
+use crate::bitgrid::BitGrid;
+use crate::export;
+use crate::recorder::Recorder;
+use crate::unboundedgrid::UnboundedBitGrid;
 
-fn pixel(c: char) -> bool {
+fn pixel(c: char) -> Result<bool, char> {
     match c {
-        '.' => false,
-        '#' => true,
-        _ => panic!("invalid input"),
+        '.' => Ok(false),
+        '#' => Ok(true),
+        _ => Err(c),
     }
 }
 
 fn load_lut(line: &str) -> Vec<bool> {
-    let mut ret = Vec::new();
-    for i in 0..512 {
-        let pixel = pixel(line.chars().nth(i).expect("invalid input"));
-        ret.push(pixel);
-    }
+    let ret: Vec<bool> = line
+        .chars()
+        .map(|c| pixel(c).expect("invalid input"))
+        .collect();
+    assert!(ret.len() == 512);
     ret
 }
 
 #[derive(Clone)]
 struct Image {
-    pixels: Array2D<bool>,
-    border: bool,
+    pixels: UnboundedBitGrid,
 }
 
 impl Image {
-    fn load(lines: &[&str]) -> Image {
-        let h = lines.len();
-        let w = lines[0].len();
-        let mut pixels = Array2D::new(w, h);
-
-        for line in lines.iter().enumerate() {
-            assert!(line.1.len() == w);
-            for c in line.1.chars().enumerate() {
-                pixels.set((c.0, line.0), pixel(c.1));
-            }
-        }
-
+    fn load(input: &str) -> Image {
+        let pixels = Array2D::parse_grid(input, pixel).expect("invalid input");
         Image {
-            pixels,
-            border: false,
+            pixels: UnboundedBitGrid::from_bits(BitGrid::from(&pixels), false),
         }
     }
 
     fn lit(&self) -> usize {
-        if self.border {
+        if self.pixels.background() {
             panic!("infinite image bits lit!");
         }
-        self.pixels.iter().filter(|b| **b).count()
+        self.pixels.count_ones()
     }
 
-    fn at(&self, x: i32, y: i32) -> bool {
-        if x < 0 || y < 0 || x >= self.pixels.width() as i32 || y >= self.pixels.height() as i32 {
-            self.border
-        } else {
-            *self.pixels.at((x as usize, y as usize))
-        }
-    }
-
-    fn enhance(&self, lut: &Vec<bool>) -> Image {
-        let w = self.pixels.width();
-        let h = self.pixels.height();
-        let mut new = Array2D::new(w + 2, h + 2);
-
-        for x in -2..w as i32 {
-            for y in -2..h as i32 {
-                let v00 = if self.at(x, y) { 1 } else { 0 };
-                let v01 = if self.at(x, y + 1) { 1 } else { 0 };
-                let v02 = if self.at(x, y + 2) { 1 } else { 0 };
-                let v10 = if self.at(x + 1, y) { 1 } else { 0 };
-                let v11 = if self.at(x + 1, y + 1) { 1 } else { 0 };
-                let v12 = if self.at(x + 1, y + 2) { 1 } else { 0 };
-                let v20 = if self.at(x + 2, y) { 1 } else { 0 };
-                let v21 = if self.at(x + 2, y + 1) { 1 } else { 0 };
-                let v22 = if self.at(x + 2, y + 2) { 1 } else { 0 };
-                let v = (v00 << 8)
-                    + (v10 << 7)
-                    + (v20 << 6)
-                    + (v01 << 5)
-                    + (v11 << 4)
-                    + (v21 << 3)
-                    + (v02 << 2)
-                    + (v12 << 1)
-                    + v22;
-                new.set(((x + 2) as usize, (y + 2) as usize), lut[v]);
+    /// Grows the image by one pixel on every side.
+    fn enhance(&self, lut: &[bool]) -> Image {
+        let background = self.pixels.background();
+        let mut new = UnboundedBitGrid::new(lut[if background { 511 } else { 0 }]);
+
+        if let Some((min, max)) = self.pixels.bounds() {
+            // the 3x3 windows of the output pixels reach one more pixel out
+            let src = self
+                .pixels
+                .region((min.0 - 2, min.1 - 2), (max.0 + 2, max.1 + 2));
+            let mut out = BitGrid::new(src.width() - 2, src.height() - 2);
+
+            // process 64 output pixels at once, reading their 3x3 windows as 9 words
+            for y in 0..out.height() {
+                for x in (0..out.width()).step_by(64) {
+                    let mut window = [0; 9];
+                    for (i, w) in window.iter_mut().enumerate() {
+                        *w = src.read_word(x + i % 3, y + i / 3);
+                    }
+
+                    let mut word = 0;
+                    for bit in 0..64.min(out.width() - x) {
+                        let v = window
+                            .iter()
+                            .fold(0, |v, w| (v << 1) | (w >> bit) as usize & 1);
+                        word |= (lut[v] as u64) << bit;
+                    }
+                    out.row_words_mut(y)[x / 64] = word;
+                }
             }
+            new.blit(&out, (min.0 - 1, min.1 - 1));
         }
 
-        let border = lut[if self.border { 511 } else { 0 }];
-
-        Image {
-            pixels: new,
-            border,
-        }
+        Image { pixels: new }
     }
 }
 
 pub fn solve() {
-    let input: Vec<&str> = include_str!("inputs/20.txt")
-        .split('\n')
-        .filter(|line| !line.is_empty())
-        .collect();
+    let input = include_str!("inputs/20.txt");
+    let (lut, img) = input.split_once("\n\n").expect("invalid input");
 
-    let lut = load_lut(input[0]);
-    let img = Image::load(&input[1..]);
+    let lut = load_lut(lut.trim());
+    let img = Image::load(img);
 
     let task1 = img.enhance(&lut).enhance(&lut).lit();
     println!("[day 20] task 1 = {}", task1);
 
+    let mut recorder =
+        export::dir().map(|_| Recorder::new(|pixels: &Array2D<bool>| pixels.to_pbm(2, |b| *b)));
+
     let mut task2 = img.clone();
-    for i in 0..50 {
-        task2 = task2.enhance(&lut);
+    for step in 0..=50 {
+        if step > 0 {
+            task2 = task2.enhance(&lut);
+        }
+        if let Some(recorder) = &mut recorder {
+            if recorder.wants(step) {
+                recorder.capture(step, &task2.pixels.to_array2d());
+            }
+        }
+    }
+    if let Some(recorder) = recorder {
+        recorder.export("day20_", "pbm");
     }
     println!("[day 20] task 2 = {}", task2.lit());
+
+    export::write("day20.pbm", || task2.pixels.to_array2d().to_pbm(2, |b| *b));
 }
@@ -1,3 +1,5 @@
+use crate::arraynd::ArrayND;
+
 #[derive(Debug, Copy, Clone)]
 struct Cuboid {
     // coords are inclusive
@@ -226,6 +228,30 @@ impl Space {
     }
 }
 
+/// Brute-force reference for `Space`, only usable for small regions.
+fn voxelized(commands: &[Command], region: &Cuboid) -> usize {
+    let mut voxels = ArrayND::<bool, 3>::new([
+        (region.x2 - region.x1) as usize,
+        (region.y2 - region.y1) as usize,
+        (region.z2 - region.z1) as usize,
+    ]);
+
+    for cmd in commands {
+        if let Some(c) = cmd.cuboid.union(region) {
+            for z in c.z1..c.z2 {
+                for y in c.y1..c.y2 {
+                    for x in c.x1..c.x2 {
+                        let pos = [x - region.x1, y - region.y1, z - region.z1];
+                        voxels[pos.map(|v| v as usize)] = cmd.on;
+                    }
+                }
+            }
+        }
+    }
+
+    voxels.iter().filter(|v| **v).count()
+}
+
 pub fn solve() {
     let commands: Vec<Command> = include_str!("inputs/22.txt")
         .split('\n')
@@ -238,9 +264,36 @@ pub fn solve() {
         space.execute(c);
     }
 
-    println!(
-        "[day 22] task 1 = {}",
-        space.total_filtered(&Cuboid::new(-50, 51, -50, 51, -50, 51))
-    );
+    let region = Cuboid::new(-50, 51, -50, 51, -50, 51);
+    let task1 = space.total_filtered(&region);
+    debug_assert_eq!(task1, voxelized(&commands, &region));
+    println!("[day 22] task 1 = {}", task1);
     println!("[day 22] task 2 = {}", space.total());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_voxels() {
+        for input in [
+            include_str!("inputs/22_a.txt"),
+            include_str!("inputs/22_b.txt"),
+        ] {
+            let commands: Vec<Command> = input
+                .split('\n')
+                .filter(|line| !line.is_empty())
+                .map(Command::new)
+                .collect();
+
+            let mut space = Space::new();
+            for c in &commands {
+                space.execute(c);
+            }
+
+            let region = Cuboid::new(-50, 51, -50, 51, -50, 51);
+            assert_eq!(space.total_filtered(&region), voxelized(&commands, &region));
+        }
+    }
+}
@@ -1,4 +1,6 @@
 use crate::array2d::Array2D;
+use crate::export;
+use crate::recorder::Recorder;
 
 #[repr(u8)]
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -9,12 +11,12 @@ enum Cell {
 }
 
 impl Cell {
-    fn from(c: char) -> Self {
+    fn from(c: char) -> Result<Self, char> {
         match c {
-            '>' => Cell::Right,
-            'v' => Cell::Down,
-            '.' => Cell::Empty,
-            _ => panic!("nay"),
+            '>' => Ok(Cell::Right),
+            'v' => Ok(Cell::Down),
+            '.' => Ok(Cell::Empty),
+            _ => Err(c),
         }
     }
 
@@ -39,14 +41,8 @@ struct Field {
 }
 
 impl Field {
-    fn new(lines: &[&str]) -> Self {
-        let mut cells = Array2D::new(lines[0].len(), lines.len());
-        for y in 0..cells.height() {
-            assert!(lines[y].len() == lines[0].len());
-            for x in 0..cells.width() {
-                cells.set((x, y), Cell::from(lines[y].chars().nth(x).unwrap()));
-            }
-        }
+    fn new(input: &str) -> Self {
+        let cells = Array2D::parse_grid(input, Cell::from).expect("nay");
         let ok = vec![false; cells.height().max(cells.width())];
         Self {
             cells,
@@ -60,13 +56,13 @@ impl Field {
         // step right
         for y in 0..self.cells.height() {
             for x in 0..self.cells.width() {
-                let nx = if x + 1 < self.cells.width() { x + 1 } else { 0 };
-                self.ok[x] = (*self.cells.at((x, y)) == Cell::Right) && (*self.cells.at((nx, y)) == Cell::Empty);
+                let next = self.cells.offset_wrapping((x, y), (1, 0));
+                self.ok[x] = (*self.cells.at((x, y)) == Cell::Right) && (*self.cells.at(next) == Cell::Empty);
             }
             for x in 0..self.cells.width() {
-                let nx = if x + 1 < self.cells.width() { x + 1 } else { 0 };
                 if self.ok[x] {
-                    self.cells.set((nx, y), *self.cells.at((x, y)));
+                    let next = self.cells.offset_wrapping((x, y), (1, 0));
+                    self.cells.set(next, *self.cells.at((x, y)));
                     self.cells.set((x, y), Cell::Empty);
                     stepped = true;
                 }
@@ -76,13 +72,13 @@ impl Field {
         // step down
         for x in 0..self.cells.width() {
             for y in 0..self.cells.height() {
-                let ny = if y + 1 < self.cells.height() { y + 1 } else { 0 };
-                self.ok[y] = (*self.cells.at((x, y)) == Cell::Down) && (*self.cells.at((x, ny)) == Cell::Empty);
+                let next = self.cells.offset_wrapping((x, y), (0, 1));
+                self.ok[y] = (*self.cells.at((x, y)) == Cell::Down) && (*self.cells.at(next) == Cell::Empty);
             }
             for y in 0..self.cells.height() {
-                let ny = if y + 1 < self.cells.height() { y + 1 } else { 0 };
                 if self.ok[y] {
-                    self.cells.set((x, ny), *self.cells.at((x, y)));
+                    let next = self.cells.offset_wrapping((x, y), (0, 1));
+                    self.cells.set(next, *self.cells.at((x, y)));
                     self.cells.set((x, y), Cell::Empty);
                     stepped = true;
                 }
@@ -92,24 +88,40 @@ impl Field {
         stepped
     }
 
+    fn to_ppm(&self, scale: usize) -> String {
+        self.cells.to_ppm(scale, |c| match c {
+            Cell::Empty => [0, 0, 64],
+            Cell::Right => [255, 160, 0],
+            Cell::Down => [0, 200, 80],
+        })
+    }
+
     fn print(&self) {
-        for y in 0..self.cells.height() {
-            for x in 0..self.cells.width() {
-                print!("{}", self.cells.at((x, y)).ch());
-            }
-            println!("");
-        }
+        print!("{}", self.cells.to_grid_string(|c| c.ch()));
     }
 }
 
 pub fn solve() {
     let input = include_str!("inputs/25.txt");
-    let lines: Vec<&str> = input.split("\n").filter(|line| !line.is_empty()).collect();
-    let mut field = Field::new(&lines);
+    let mut field = Field::new(input);
+    export::write("day25.ppm", || field.to_ppm(4));
+
+    let mut recorder = export::dir().map(|_| {
+        Recorder::new(|cells: &Array2D<Cell>| cells.to_grid_string(|c| c.ch())).stride(10)
+    });
+    if let Some(recorder) = &mut recorder {
+        recorder.capture(0, &field.cells);
+    }
 
     let mut count = 0;
     while field.step() {
         count += 1;
+        if let Some(recorder) = &mut recorder {
+            recorder.capture(count, &field.cells);
+        }
+    }
+    if let Some(recorder) = recorder {
+        recorder.export_animation("day25.txt", "--- step");
     }
     println!("[day 25] {}", count + 1);
 }
\ No newline at end of file
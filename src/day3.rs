@@ -1,21 +1,22 @@
-fn most_common(numbers: &Vec<&str>) -> Vec<char> {
-    let mut frequencies1 = Vec::new();
-    let mut total = 0;
-    for number in numbers {
-        for ch in number.chars().enumerate() {
-            if ch.0 >= frequencies1.len() {
-                frequencies1.push(0);
-            }
-            if ch.1 == '1' {
-                frequencies1[ch.0] += 1;
-            }
-        }
-        total += 1;
-    }
+use crate::counter::Counter;
 
-    frequencies1
+fn most_common(numbers: &[&str]) -> Vec<char> {
+    // how often each bit position is set
+    let ones: Counter<usize> = numbers
         .iter()
-        .map(|freq| if freq * 2 >= total { '1' } else { '0' })
+        .flat_map(|number| number.char_indices().filter(|ch| ch.1 == '1'))
+        .map(|ch| ch.0)
+        .collect();
+    let width = numbers.iter().map(|number| number.len()).max().unwrap_or(0);
+
+    (0..width)
+        .map(|i| {
+            if ones.count(&i) * 2 >= numbers.len() {
+                '1'
+            } else {
+                '0'
+            }
+        })
         .collect()
 }
 
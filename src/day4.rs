@@ -1,114 +1,400 @@
+use crate::array2d::Array2D;
+use crate::flatmap::HashFlatMap;
+
+/// A set of marked cells that wins a board. If several are completed at
+/// once, the first one in this order is reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Line {
+    Row(usize),
+    Column(usize),
+    /// Top left to bottom right.
+    Diagonal,
+    /// Top right to bottom left.
+    AntiDiagonal,
+    FourCorners,
+    FullCard,
+}
+
+/// Which lines win a board. Diagonals only count on square boards.
+#[derive(Clone, Copy)]
+struct Rules {
+    rows: bool,
+    columns: bool,
+    diagonals: bool,
+    full_card: bool,
+    four_corners: bool,
+}
+
+impl Rules {
+    fn standard() -> Self {
+        Self {
+            rows: true,
+            columns: true,
+            diagonals: false,
+            full_card: false,
+            four_corners: false,
+        }
+    }
+}
+
+/// Board `board` won when `number`, the draw at index `draw`, completed `line`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Win {
+    draw: usize,
+    number: u32,
+    board: usize,
+    line: Line,
+    score: u32,
+}
+
 #[derive(Clone)]
 struct Board {
-    numbers: Vec<(u32, bool)>,
+    numbers: Array2D<u32>,
+}
+
+/// Where each number is found, as board index and cell position.
+type Index = HashFlatMap<u32, Vec<(usize, (usize, usize))>>;
+
+/// The marked cells of a board during a game, and how many cells of
+/// each line are marked.
+struct Hits {
+    marked: Array2D<bool>,
+    rows: Vec<usize>,
+    columns: Vec<usize>,
+    diagonal: usize,
+    anti_diagonal: usize,
+    corners: usize,
+    total: usize,
+    unmarked_sum: u32,
 }
 
-#[derive(Clone)]
 struct Data {
     draws: Vec<u32>,
     boards: Vec<Board>,
 }
 
 impl Board {
-    fn new(lines: &[&str]) -> Self {
-        let mut numbers = Vec::new();
+    /// Parses whitespace separated numbers, one row per line. All rows
+    /// must have the same length.
+    fn new(lines: &[&str]) -> Result<Self, ()> {
+        let rows: Vec<Vec<u32>> = lines
+            .iter()
+            .map(|line| line.split_whitespace().map(|s| s.parse::<u32>()).collect())
+            .collect::<Result<_, _>>()
+            .map_err(|_| ())?;
 
-        for line in lines {
-            let nums = line
-                .split(' ')
-                .filter(|s| !s.is_empty())
-                .map(|s| s.parse::<u32>().unwrap());
+        let width = rows.first().ok_or(())?.len();
+        if width == 0 || rows.iter().any(|row| row.len() != width) {
+            return Err(());
+        }
 
-            for num in nums {
-                numbers.push((num, false));
+        let mut numbers = Array2D::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, num) in row.iter().enumerate() {
+                numbers[(x, y)] = *num;
             }
         }
 
-        assert!(numbers.len() == 25);
-
-        Self { numbers }
+        Ok(Self { numbers })
     }
 
-    fn at(&self, x: usize, y: usize) -> (u32, bool) {
-        self.numbers[y * 5 + x]
-    }
+    /// The first of the lines allowed by `rules` that is fully marked.
+    fn won_line(&self, hits: &Hits, rules: &Rules) -> Option<Line> {
+        let (w, h) = (self.numbers.width(), self.numbers.height());
+        let marked = |x, y| *hits.marked.at((x, y));
 
-    fn solved(&self) -> bool {
-        for i in 0..5 {
-            let mut solved_x = true;
-            let mut solved_y = true;
-            for j in 0..5 {
-                solved_x &= self.at(i, j).1;
-                solved_y &= self.at(j, i).1;
+        if rules.rows {
+            if let Some(y) = (0..h).find(|y| (0..w).all(|x| marked(x, *y))) {
+                return Some(Line::Row(y));
+            }
+        }
+        if rules.columns {
+            if let Some(x) = (0..w).find(|x| (0..h).all(|y| marked(*x, y))) {
+                return Some(Line::Column(x));
             }
-            if solved_x || solved_y {
-                return true;
+        }
+        if rules.diagonals && w == h {
+            if (0..w).all(|i| marked(i, i)) {
+                return Some(Line::Diagonal);
+            }
+            if (0..w).all(|i| marked(w - 1 - i, i)) {
+                return Some(Line::AntiDiagonal);
             }
         }
-        return false;
+        if rules.four_corners
+            && [(0, 0), (w - 1, 0), (0, h - 1), (w - 1, h - 1)]
+                .iter()
+                .all(|(x, y)| marked(*x, *y))
+        {
+            return Some(Line::FourCorners);
+        }
+        if rules.full_card && hits.marked.iter().all(|m| *m) {
+            return Some(Line::FullCard);
+        }
+        None
     }
 
-    fn unmarked_sum(&self) -> u32 {
-        self.numbers
-            .iter()
-            .filter_map(|n| if !n.1 { Some(n.0) } else { None })
-            .sum()
+    fn hits(&self) -> Hits {
+        Hits {
+            marked: Array2D::new(self.numbers.width(), self.numbers.height()),
+            rows: vec![0; self.numbers.height()],
+            columns: vec![0; self.numbers.width()],
+            diagonal: 0,
+            anti_diagonal: 0,
+            corners: 0,
+            total: 0,
+            unmarked_sum: self.numbers.iter().sum(),
+        }
     }
 
-    fn mark(&mut self, num: u32) -> bool {
-        let was_solved = self.solved();
-        for n in &mut self.numbers {
-            if n.0 == num {
-                n.1 = true;
+    /// Marks the cell at `pos`, returning the lines allowed by `rules`
+    /// that this completes.
+    fn mark(&self, pos: (usize, usize), hits: &mut Hits, rules: &Rules) -> Vec<Line> {
+        let (w, h) = (self.numbers.width(), self.numbers.height());
+        let (x, y) = pos;
+        if hits.marked[pos] {
+            return Vec::new();
+        }
+        hits.marked[pos] = true;
+        hits.unmarked_sum -= self.numbers[pos];
+
+        let mut ret = Vec::new();
+        let mut hit = |count: &mut usize, needed: usize, allowed: bool, line: Line| {
+            *count += 1;
+            if allowed && *count == needed {
+                ret.push(line);
             }
+        };
+        hit(&mut hits.rows[y], w, rules.rows, Line::Row(y));
+        hit(&mut hits.columns[x], h, rules.columns, Line::Column(x));
+        if w == h && x == y {
+            hit(&mut hits.diagonal, w, rules.diagonals, Line::Diagonal);
         }
-        !was_solved && self.solved()
+        if w == h && x == w - 1 - y {
+            hit(
+                &mut hits.anti_diagonal,
+                w,
+                rules.diagonals,
+                Line::AntiDiagonal,
+            );
+        }
+        if (x == 0 || x == w - 1) && (y == 0 || y == h - 1) {
+            // corners coincide on boards only one cell wide or high
+            let needed = w.min(2) * h.min(2);
+            hit(
+                &mut hits.corners,
+                needed,
+                rules.four_corners,
+                Line::FourCorners,
+            );
+        }
+        hit(&mut hits.total, w * h, rules.full_card, Line::FullCard);
+        ret
     }
 }
 
 impl Data {
-    fn new(text: &str) -> Self {
-        let mut lines = text.split('\n');
+    /// Parses the draws, followed by boards separated by empty lines.
+    /// Boards may have any size, even differing from each other.
+    fn new(text: &str) -> Result<Self, ()> {
+        let mut blocks = text.split("\n\n");
 
-        let draws = lines
+        let draws = blocks
             .next()
-            .unwrap()
+            .ok_or(())?
+            .trim()
             .split(',')
-            .map(|s| s.parse::<u32>().unwrap())
-            .collect();
-
-        let mut boards = Vec::new();
-
-        let mut board_lines = Vec::new();
-        for line in lines {
-            if line.is_empty() && board_lines.len() == 5 {
-                boards.push(Board::new(&board_lines));
-                board_lines.clear();
-            } else if !line.is_empty() {
-                board_lines.push(line);
+            .map(|s| s.parse::<u32>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| ())?;
+
+        let boards = blocks
+            .filter(|block| !block.trim().is_empty())
+            .map(|block| {
+                let lines: Vec<&str> = block.lines().filter(|l| !l.is_empty()).collect();
+                Board::new(&lines)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { draws, boards })
+    }
+
+    fn index(&self) -> Index {
+        let mut ret = Index::new();
+        for (idx, board) in self.boards.iter().enumerate() {
+            for (pos, cell) in board.numbers.indexed_iter() {
+                ret.entry(*cell).or_default().push((idx, pos));
             }
         }
+        ret
+    }
 
-        Self { draws, boards }
+    /// Draws numbers until every board won, returning the wins in the order
+    /// they happened. Boards that already won keep being marked, but can't
+    /// win again. Each draw only visits the cells holding its number.
+    fn play(&self, rules: &Rules) -> Vec<Win> {
+        let index = self.index();
+        let mut hits: Vec<Hits> = self.boards.iter().map(|b| b.hits()).collect();
+        let mut wins = Vec::new();
+        let mut won = vec![false; self.boards.len()];
+
+        for (draw, number) in self.draws.iter().enumerate() {
+            if wins.len() == self.boards.len() {
+                break;
+            }
+
+            let mut completed: Vec<(usize, Line)> = Vec::new();
+            for &(idx, pos) in index.get(number).map_or(&[][..], |v| v.as_slice()) {
+                let lines = self.boards[idx].mark(pos, &mut hits[idx], rules);
+                completed.extend(lines.into_iter().map(|line| (idx, line)));
+            }
+
+            // the earliest board first, with its first line
+            completed.sort();
+            for (idx, line) in completed {
+                if won[idx] {
+                    continue;
+                }
+                won[idx] = true;
+                debug_assert_eq!(self.boards[idx].won_line(&hits[idx], rules), Some(line));
+                wins.push(Win {
+                    draw,
+                    number: *number,
+                    board: idx,
+                    line,
+                    score: hits[idx].unmarked_sum * number,
+                });
+            }
+        }
+
+        wins
     }
 }
 
 pub fn solve() {
     let input = include_str!("inputs/4.txt");
-    let mut data = Data::new(input);
+    let data = Data::new(input).unwrap();
 
-    let mut solved = Vec::new();
+    let wins = data.play(&Rules::standard());
 
-    for draw in &data.draws {
-        for board in &mut data.boards {
-            if board.mark(*draw) {
-                solved.push((board.clone(), *draw));
-            }
-        }
+    println!("[day  4] part 1 = {}", wins.first().unwrap().score);
+    println!("[day  4] part 2 = {}", wins.last().unwrap().score);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Data, Line, Rules, Win};
+
+    const EXAMPLE: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7
+";
+
+    #[test]
+    fn test_example() {
+        let data = Data::new(EXAMPLE).unwrap();
+        let wins = data.play(&Rules::standard());
+        assert_eq!(
+            wins.first(),
+            Some(&Win {
+                draw: 11,
+                number: 24,
+                board: 2,
+                line: Line::Row(0),
+                score: 4512
+            })
+        );
+        assert_eq!(wins.last().map(|w| (w.board, w.score)), Some((1, 1924)));
+        assert_eq!(wins.len(), 3);
+
+        // every game starts from unmarked boards
+        assert_eq!(data.play(&Rules::standard()), wins);
+    }
+
+    #[test]
+    fn test_rules() {
+        let data =
+            Data::new("1,5,9,3,7,2,4,6,8\n\n1 2 3\n4 5 6\n7 8 9\n\n1 2 3 4\n5 6 7 8\n").unwrap();
+        assert_eq!(data.boards[1].numbers.width(), 4);
+        assert_eq!(data.boards[1].numbers.height(), 2);
+
+        let only = |set: fn(&mut Rules)| {
+            let mut rules = Rules {
+                rows: false,
+                columns: false,
+                diagonals: false,
+                full_card: false,
+                four_corners: false,
+            };
+            set(&mut rules);
+            rules
+        };
+        let lines = |data: &Data, rules: Rules| -> Vec<(usize, usize, Line)> {
+            let wins = data.play(&rules);
+            wins.iter().map(|w| (w.draw, w.board, w.line)).collect()
+        };
+
+        let diagonals = only(|r| r.diagonals = true);
+        assert_eq!(lines(&data, diagonals), vec![(2, 0, Line::Diagonal)]);
+
+        let corners = only(|r| r.four_corners = true);
+        assert_eq!(
+            lines(&data, corners),
+            vec![(4, 0, Line::FourCorners), (8, 1, Line::FourCorners)]
+        );
+
+        let full = only(|r| r.full_card = true);
+        assert_eq!(
+            lines(&data, full),
+            vec![(8, 0, Line::FullCard), (8, 1, Line::FullCard)]
+        );
+
+        assert_eq!(
+            lines(&data, Rules::standard()),
+            vec![(1, 1, Line::Column(0)), (5, 0, Line::Row(0))]
+        );
+
+        // a single column only has two corners
+        let column = Data::new("2,1\n\n1\n5\n2\n").unwrap();
+        assert_eq!(lines(&column, corners), vec![(1, 0, Line::FourCorners)]);
+
+        assert!(Data::new("1,2\n\n1 2\n3\n").is_err());
     }
 
-    let first = solved.first().unwrap();
-    let last = solved.last().unwrap();
-    println!("[day  4] part 1 = {}", first.0.unmarked_sum() * first.1);
-    println!("[day  4] part 2 = {}", last.0.unmarked_sum() * last.1);
+    #[test]
+    fn test_repeated_numbers() {
+        // 1 appears twice on the first board, completing a column, and three
+        // times on the second, completing a row and a column at once
+        let data = Data::new(
+            "1,3,9
+
+1 2
+1 3
+
+9 1
+1 1
+",
+        )
+        .unwrap();
+        let wins = data.play(&Rules::standard());
+        let lines: Vec<_> = wins.iter().map(|w| (w.draw, w.board, w.line)).collect();
+        assert_eq!(lines, vec![(0, 0, Line::Column(0)), (0, 1, Line::Row(1))]);
+        assert_eq!(wins[1].score, 9);
+    }
 }
@@ -1,28 +1,26 @@
-struct Landscape {
-    width: i32,
-    height: i32,
-    data: Vec<u32>,
+use crate::grid::{prefer_dense, Grid};
+use crate::sparsegrid::SparseGrid;
+use crate::unboundedgrid::UnboundedGrid;
+
+struct Landscape<G: Grid<u32>> {
+    data: G,
 }
 
-impl Landscape {
-    fn new(sz: i32) -> Self {
-        let mut data = Vec::new();
-        data.resize((sz * sz) as _, 0);
+impl<G: Grid<u32>> Landscape<G> {
+    fn new(width: usize, height: usize) -> Self {
         Self {
-            width: sz,
-            height: sz,
-            data,
+            data: G::new(width, height),
         }
     }
 
     fn mark(&mut self, x: i32, y: i32) {
-        assert!(x < self.width);
-        assert!(y < self.height);
-        self.data[(y * self.width + x) as usize] += 1;
+        assert!(x >= 0 && y >= 0);
+        let pos = (x as usize, y as usize);
+        self.data.set(pos, self.data.value(pos) + 1);
     }
 
     fn overlap(&self) -> usize {
-        self.data.iter().filter(|d| **d >= 2).count()
+        self.data.occupied().filter(|d| d.1 >= 2).count()
     }
 }
 
@@ -63,19 +61,10 @@ impl Line {
     }
 }
 
-pub fn solve() {
-    let input = include_str!("inputs/5.txt");
-
-    let mut lines = Vec::new();
-    for s in input.split('\n') {
-        if let Ok(line) = Line::new(s) {
-            lines.push(line);
-        }
-    }
-
-    let mut board1 = Landscape::new(1024);
-    let mut board2 = Landscape::new(1024);
-    for line in &lines {
+fn overlaps<G: Grid<u32>>(lines: &[Line], width: usize, height: usize) -> (usize, usize) {
+    let mut board1 = Landscape::<G>::new(width, height);
+    let mut board2 = Landscape::<G>::new(width, height);
+    for line in lines {
         if line.is_vert() {
             let y1 = line.y1.min(line.y2);
             let y2 = line.y1.max(line.y2);
@@ -102,6 +91,28 @@ pub fn solve() {
         }
     }
 
-    println!("[day  5] task 1 = {}", board1.overlap());
-    println!("[day  5] task 2 = {}", board2.overlap());
+    (board1.overlap(), board2.overlap())
+}
+
+pub fn solve() {
+    let input = include_str!("inputs/5.txt");
+
+    let mut lines = Vec::new();
+    for s in input.split('\n') {
+        if let Ok(line) = Line::new(s) {
+            lines.push(line);
+        }
+    }
+
+    let width = 1 + lines.iter().map(|l| l.x1.max(l.x2)).max().unwrap_or(0) as usize;
+    let height = 1 + lines.iter().map(|l| l.y1.max(l.y2)).max().unwrap_or(0) as usize;
+
+    let (task1, task2) = if prefer_dense(width, height) {
+        overlaps::<UnboundedGrid<u32>>(&lines, width, height)
+    } else {
+        overlaps::<SparseGrid<u32>>(&lines, width, height)
+    };
+
+    println!("[day  5] task 1 = {}", task1);
+    println!("[day  5] task 2 = {}", task2);
 }
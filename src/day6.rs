@@ -1,48 +1,227 @@
-struct Fishes {
-    count: [usize; 9],
+use crate::counter::Counter;
+use num::{FromPrimitive, Integer};
+
+type Matrix<T> = Vec<Vec<T>>;
+
+/// Number of fish per timer value. A fish whose timer runs out has its
+/// timer set to `reset` and spawns a fish with timer `newborn`.
+#[derive(Clone)]
+struct Fishes<T> {
+    count: Vec<T>,
+    reset: usize,
+    newborn: usize,
+    /// If set, counts are only kept modulo this.
+    modulus: Option<T>,
 }
 
-impl Fishes {
-    fn new(input: &str) -> Self {
-        let mut count = [0; 9];
+impl<T: Integer + Clone + FromPrimitive> Fishes<T> {
+    fn new(input: &str, reset: usize, newborn: usize) -> Self {
+        let ages: Counter<usize> = input
+            .split(['\n', ','])
+            .filter_map(|s| s.parse::<usize>().ok())
+            .collect();
 
-        for line in input.split('\n') {
-            for num in line.split(',').filter_map(|s| s.parse::<usize>().ok()) {
-                assert!(num < 9);
-                count[num] += 1;
-            }
+        let slots = reset.max(newborn) + 1;
+        let mut count = vec![T::zero(); slots];
+        for (age, n) in ages.iter() {
+            assert!(*age < slots);
+            count[*age] = T::from_usize(n).unwrap();
+        }
+
+        Self {
+            count,
+            reset,
+            newborn,
+            modulus: None,
+        }
+    }
+
+    fn with_modulus(mut self, modulus: T) -> Self {
+        self.modulus = Some(modulus);
+        self.count = self.count.iter().map(|n| self.reduce(n.clone())).collect();
+        self
+    }
+
+    fn reduce(&self, n: T) -> T {
+        match &self.modulus {
+            Some(modulus) => n.mod_floor(modulus),
+            None => n,
+        }
+    }
+
+    /// `a + b`, reduced. Both must be reduced already; with a modulus the
+    /// sum is never formed, so that any modulus `T` can hold works.
+    fn add(&self, a: T, b: T) -> T {
+        match &self.modulus {
+            Some(modulus) if a >= modulus.clone() - b.clone() => a - (modulus.clone() - b),
+            _ => a + b,
         }
+    }
 
-        Self { count }
+    /// `a * b`, reduced. Both must be reduced already; with a modulus this
+    /// adds up doublings of `a` so that no product can overflow.
+    fn mul(&self, a: T, b: T) -> T {
+        if self.modulus.is_none() {
+            return a * b;
+        }
+        let two = T::one() + T::one();
+        let (mut a, mut b) = (a, b);
+        let mut ret = T::zero();
+        while !b.is_zero() {
+            if b.is_odd() {
+                ret = self.add(ret, a.clone());
+            }
+            a = self.add(a.clone(), a);
+            b = b / two.clone();
+        }
+        ret
     }
 
     fn step(&mut self) {
-        let mut new_count = [0; 9];
-        for i in 0..8 {
-            new_count[i] = self.count[i + 1];
+        let spawning = self.count.remove(0);
+        self.count.push(T::zero());
+        self.count[self.reset] = self.add(self.count[self.reset].clone(), spawning.clone());
+        self.count[self.newborn] = self.add(self.count[self.newborn].clone(), spawning);
+    }
+
+    fn count(&self) -> T {
+        self.count
+            .iter()
+            .fold(T::zero(), |acc, n| self.add(acc, n.clone()))
+    }
+
+    /// The matrix turning the counts of one day into those of the next.
+    fn transition(&self) -> Matrix<T> {
+        let slots = self.count.len();
+        let one = self.reduce(T::one());
+        let mut ret = vec![vec![T::zero(); slots]; slots];
+        for (i, row) in ret.iter_mut().enumerate().take(slots - 1) {
+            row[i + 1] = one.clone();
+        }
+        ret[self.reset][0] = self.add(ret[self.reset][0].clone(), one.clone());
+        ret[self.newborn][0] = self.add(ret[self.newborn][0].clone(), one);
+        ret
+    }
+
+    fn multiply(&self, a: &Matrix<T>, b: &Matrix<T>) -> Matrix<T> {
+        let n = a.len();
+        let mut ret = vec![vec![T::zero(); n]; n];
+        for (i, row) in ret.iter_mut().enumerate() {
+            for (j, cell) in row.iter_mut().enumerate() {
+                *cell = (0..n).fold(T::zero(), |acc, k| {
+                    self.add(acc, self.mul(a[i][k].clone(), b[k][j].clone()))
+                });
+            }
         }
-        new_count[6] += self.count[0];
-        new_count[8] += self.count[0];
-        self.count = new_count;
+        ret
     }
 
-    fn count(&self) -> usize {
-        self.count.iter().sum()
+    /// The number of fish after `days`, by squaring the transition matrix
+    /// instead of stepping day by day.
+    fn count_after(&self, mut days: u64) -> T {
+        let slots = self.count.len();
+        let mut power = self.transition();
+        let mut total = vec![vec![T::zero(); slots]; slots];
+        for (i, row) in total.iter_mut().enumerate() {
+            row[i] = self.reduce(T::one());
+        }
+        while days > 0 {
+            if days % 2 == 1 {
+                total = self.multiply(&total, &power);
+            }
+            power = self.multiply(&power, &power);
+            days /= 2;
+        }
+
+        total
+            .iter()
+            .flat_map(|row| row.iter().zip(&self.count))
+            .fold(T::zero(), |acc, (m, n)| {
+                self.add(acc, self.mul(m.clone(), n.clone()))
+            })
     }
 }
 
 pub fn solve() {
     let input = include_str!("inputs/6.txt");
-    let mut fish1 = Fishes::new(input);
-    let mut fish2 = Fishes::new(input);
+    let fishes = Fishes::<usize>::new(input, 6, 8);
+    let task1 = fishes.count_after(80);
+    let task2 = fishes.count_after(256);
+
+    // cross-check against simulating day by day, and against counting
+    // modulo a prime
+    debug_assert_eq!(task1, {
+        let mut stepped = fishes.clone();
+        (0..80).for_each(|_| stepped.step());
+        stepped.count()
+    });
+    let modulus = 1_000_000_007;
+    debug_assert_eq!(
+        fishes.clone().with_modulus(modulus).count_after(256),
+        task2 % modulus
+    );
+
+    println!("[day  6] task 1 = {}", task1);
+    println!("[day  6] task 2 = {}", task2);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Fishes;
+    use num::BigUint;
 
-    for day in 0..80 {
-        fish1.step();
+    #[test]
+    fn test_example() {
+        let fishes = Fishes::<u64>::new("3,4,3,1,2\n", 6, 8);
+        assert_eq!(fishes.count_after(18), 26);
+        assert_eq!(fishes.count_after(80), 5934);
+        assert_eq!(fishes.count_after(256), 26984457539);
     }
-    for day in 0..256 {
-        fish2.step();
+
+    #[test]
+    fn test_timers() {
+        for (reset, newborn) in [(6, 8), (2, 3), (4, 2), (2, 2)] {
+            let mut stepped = Fishes::<u64>::new("0,1,1,2", reset, newborn);
+            let fishes = Fishes::<u64>::new("0,1,1,2", reset, newborn);
+            for day in 0..40 {
+                assert_eq!(stepped.count(), fishes.count_after(day));
+                stepped.step();
+            }
+        }
+
+        // everybody spawns every day
+        let doubling = Fishes::<u64>::new("0,0", 0, 0);
+        assert_eq!(doubling.count_after(10), 2 * 1024);
+        for modulus in [1, 2, 3] {
+            let mut stepped = doubling.clone().with_modulus(modulus);
+            (0..10).for_each(|_| stepped.step());
+            assert_eq!(stepped.count(), 2 * 1024 % modulus);
+            let fishes = doubling.clone().with_modulus(modulus);
+            assert_eq!(fishes.count_after(10), 2 * 1024 % modulus);
+        }
     }
 
-    println!("[day  6] task 1 = {}", fish1.count());
-    println!("[day  6] task 2 = {}", fish2.count());
+    #[test]
+    fn test_large_counts() {
+        let big = Fishes::<BigUint>::new("3,4,3,1,2", 6, 8).count_after(1000);
+        assert!(big.bits() > 128);
+
+        let modulus = 1_000_000_007u64;
+        let fishes = Fishes::<u64>::new("3,4,3,1,2", 6, 8).with_modulus(modulus);
+        let expected = &big % BigUint::from(modulus);
+        assert_eq!(BigUint::from(fishes.count_after(1000)), expected);
+
+        let mut stepped = Fishes::<u64>::new("3,4,3,1,2", 6, 8).with_modulus(modulus);
+        for _ in 0..1000 {
+            stepped.step();
+        }
+        assert_eq!(stepped.count(), fishes.count_after(1000));
+
+        // products of counts this large don't fit into 64 bits
+        for modulus in [1 << 40, u64::MAX - 58, u64::MAX] {
+            let fishes = Fishes::<u64>::new("3,4,3,1,2", 6, 8).with_modulus(modulus);
+            let expected = &big % BigUint::from(modulus);
+            assert_eq!(BigUint::from(fishes.count_after(1000)), expected);
+        }
+    }
 }
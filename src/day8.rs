@@ -1,198 +1,269 @@
-#[derive(Clone, Debug)]
-struct Digit {
-    enabled: Vec<usize>,
+const DIGITS: [(char, &str); 10] = [
+    ('0', "abcefg"),
+    ('1', "cf"),
+    ('2', "acdeg"),
+    ('3', "acdfg"),
+    ('4', "bcdf"),
+    ('5', "abdfg"),
+    ('6', "abdefg"),
+    ('7', "acf"),
+    ('8', "abcdefg"),
+    ('9', "abcdfg"),
+];
+
+/// A segment display: the names of its segments and the symbols it can
+/// show, each given by the segments it lights up.
+struct Display {
+    segments: Vec<char>,
+    glyphs: Vec<(char, u32)>,
 }
 
-impl Digit {
-    fn new(s: &str) -> Self {
-        let mut enabled: Vec<usize> = s
-            .chars()
-            .map(|c| {
-                let seg = c as usize - 'a' as usize;
-                assert!(seg < 7);
-                seg
-            })
-            .collect();
-        enabled.sort();
-
-        Self { enabled }
-    }
-
-    fn original(digit: usize) -> Self {
-        assert!(digit < 10);
-        match digit {
-            0 => Self::new("abcefg"),
-            1 => Self::new("cf"),
-            2 => Self::new("acdeg"),
-            3 => Self::new("acdfg"),
-            4 => Self::new("bcdf"),
-            5 => Self::new("abdfg"),
-            6 => Self::new("abdefg"),
-            7 => Self::new("acf"),
-            8 => Self::new("abcdefg"),
-            9 => Self::new("abcdfg"),
-            _ => panic!("no such digit"),
+impl Display {
+    fn new(segments: &str, glyphs: &[(char, &str)]) -> Self {
+        let segments: Vec<char> = segments.chars().collect();
+        assert!(segments.len() <= 32, "too many segments");
+        let mut ret = Self {
+            segments,
+            glyphs: Vec::new(),
+        };
+        for (symbol, lit) in glyphs {
+            let mask = ret.mask(lit).expect("unknown segment");
+            assert!(ret.glyphs.iter().all(|g| g.1 != mask), "duplicate glyph");
+            ret.glyphs.push((*symbol, mask));
         }
+        ret
+    }
+
+    /// The usual seven segments `a` to `g`:
+    /// ```text
+    ///  aaaa
+    /// b    c
+    /// b    c
+    ///  dddd
+    /// e    f
+    /// e    f
+    ///  gggg
+    /// ```
+    fn decimal() -> Self {
+        Self::new("abcdefg", &DIGITS)
+    }
+
+    /// The segments named in `lit`, or `None` if it names any other.
+    fn mask(&self, lit: &str) -> Option<u32> {
+        lit.chars().try_fold(0, |mask, c| {
+            let seg = self.segments.iter().position(|s| *s == c)?;
+            Some(mask | 1 << seg)
+        })
+    }
+
+    /// Whether no other glyph lights up as many segments as `pattern`.
+    fn unique_length(&self, pattern: u32) -> bool {
+        let len = pattern.count_ones();
+        self.glyphs
+            .iter()
+            .filter(|g| g.1.count_ones() == len)
+            .count()
+            == 1
     }
 
-    fn count(&self) -> usize {
-        self.enabled.len()
+    /// The wire each segment is connected to, given all glyphs as `observed`
+    /// in some order.
+    fn wiring(&self, observed: &[u32]) -> Result<Vec<usize>, DecodeError> {
+        let glyphs: Vec<u32> = self.glyphs.iter().map(|g| g.1).collect();
+        let mut wirings = find_wirings(&glyphs, observed, self.segments.len(), 2);
+        match wirings.len() {
+            0 => Err(DecodeError::Inconsistent),
+            1 => Ok(wirings.pop().unwrap()),
+            _ => Err(DecodeError::Ambiguous),
+        }
     }
 
-    fn segment_mask(&self) -> usize {
-        self.enabled.iter().map(|seg| 1 << seg).sum()
+    /// The glyph that lights up the wires of `pattern`.
+    fn glyph(&self, pattern: u32, wiring: &[usize]) -> Option<char> {
+        self.glyphs
+            .iter()
+            .find(|g| warp(g.1, wiring) == pattern)
+            .map(|g| g.0)
     }
 }
 
-#[derive(Clone, Debug)]
-struct Reading {
-    patterns: Vec<Digit>,
-    output: Vec<Digit>,
+/// Why a line isn't a reading of a display.
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    /// There is no ` | ` between the patterns and the output.
+    MissingOutput,
+    /// `pattern` lights a wire that isn't named like a segment.
+    UnknownWire { pattern: String },
+    /// There are `found` patterns instead of one per glyph.
+    PatternCount { found: usize },
 }
 
-impl Reading {
-    fn new(line: &str) -> Result<Self, ()> {
-        let mut parts = line.split(" | ");
-        let mut patterns_iter = parts.next().ok_or(())?.split(' ');
-        let mut output_iter = parts.next().ok_or(())?.split(' ');
+/// Why the wiring of a reading couldn't be determined.
+#[derive(Debug, PartialEq)]
+enum DecodeError {
+    /// No wiring turns the glyphs into the observed patterns.
+    Inconsistent,
+    /// More than one wiring does.
+    Ambiguous,
+    /// The output pattern at `index` isn't a glyph under the found wiring.
+    UnknownOutput { index: usize },
+}
 
-        let mut patterns = Vec::new();
-        let mut output = Vec::new();
+/// The wires lit up by `glyph` if segment `i` is connected to `wiring[i]`.
+fn warp(glyph: u32, wiring: &[usize]) -> u32 {
+    wiring
+        .iter()
+        .enumerate()
+        .filter(|(seg, _)| glyph & (1 << seg) != 0)
+        .map(|(_, wire)| 1 << wire)
+        .sum()
+}
 
-        for s in patterns_iter {
-            patterns.push(Digit::new(s));
-        }
-        for s in output_iter {
-            output.push(Digit::new(s));
-        }
+/// How many of `patterns` of each length light up `segment`.
+fn signature(patterns: &[u32], segment: usize) -> [u8; 33] {
+    let mut ret = [0; 33];
+    for p in patterns.iter().filter(|p| *p & (1 << segment) != 0) {
+        ret[p.count_ones() as usize] += 1;
+    }
+    ret
+}
 
-        assert!(patterns.len() == 10);
-        assert!(output.len() == 4);
+/// Finds up to `limit` ways to map each of the `segments` segments of
+/// `glyphs` to a wire such that the glyphs become exactly `observed`.
+///
+/// A segment can only go to a wire that takes part in as many patterns of
+/// each length, which usually leaves a single candidate. The remaining
+/// choices are searched, dropping any partial wiring that makes a glyph
+/// leave all observed patterns of its length.
+fn find_wirings(
+    glyphs: &[u32],
+    observed: &[u32],
+    segments: usize,
+    limit: usize,
+) -> Vec<Vec<usize>> {
+    let candidates: Vec<Vec<usize>> = (0..segments)
+        .map(|seg| {
+            let sig = signature(glyphs, seg);
+            (0..segments)
+                .filter(|wire| signature(observed, *wire) == sig)
+                .collect()
+        })
+        .collect();
 
-        Ok(Self { patterns, output })
-    }
+    let mut sorted = observed.to_vec();
+    sorted.sort();
+    let mut ret = Vec::new();
+    let mut wiring = Vec::new();
+    search_wirings(glyphs, &sorted, &candidates, &mut wiring, limit, &mut ret);
+    ret
+}
 
-    fn solve(&self) -> i32 {
-        let patterns_with_len =
-            |n| -> Vec<&Digit> { self.patterns.iter().filter(|p| p.count() == n).collect() };
-        let pattern1 = *patterns_with_len(2).first().unwrap();
-        let pattern4 = *patterns_with_len(4).first().unwrap();
-        let pattern7 = *patterns_with_len(3).first().unwrap();
-        let pattern8 = *patterns_with_len(7).first().unwrap();
-        let patterns235: Vec<&Digit> = patterns_with_len(5);
-
-        let mask1 = pattern1.segment_mask();
-        let mask4 = pattern4.segment_mask();
-        let mask7 = pattern7.segment_mask();
-        let mask8 = pattern8.segment_mask();
-
-        // which segments can map to which wires?
-        let cf = mask1;
-        let a = mask7 & !mask1;
-        let bd = mask4 & !mask7;
-        let adg = patterns235
-            .iter()
-            .map(|d| d.segment_mask())
-            .reduce(|acc, v| acc & v)
-            .unwrap();
-        let e = mask8 & !(adg | cf | bd);
-        let g = adg & !(a | bd);
-        let b = bd & !adg;
-        let d = adg & mask4;
-
-        let mut segcount = [0; 7];
-        for p in &self.patterns {
-            for seg in &p.enabled {
-                segcount[*seg] += 1;
-            }
+fn search_wirings(
+    glyphs: &[u32],
+    observed: &[u32],
+    candidates: &[Vec<usize>],
+    wiring: &mut Vec<usize>,
+    limit: usize,
+    found: &mut Vec<Vec<usize>>,
+) {
+    if wiring.len() == candidates.len() {
+        let mut warped: Vec<u32> = glyphs.iter().map(|g| warp(*g, wiring)).collect();
+        warped.sort();
+        if warped == observed {
+            found.push(wiring.clone());
         }
+        return;
+    }
 
-        let c = 1usize
-            << pattern1
-                .enabled
-                .iter()
-                .filter(|seg| segcount[**seg] == 8)
-                .next()
-                .unwrap();
-        let f = 1usize
-            << pattern1
-                .enabled
-                .iter()
-                .filter(|seg| segcount[**seg] == 9)
-                .next()
-                .unwrap();
-
-        assert!(cf.count_ones() == 2);
-        assert!(bd.count_ones() == 2);
-        assert!(adg.count_ones() == 3);
-        assert!(a.count_ones() == 1);
-        assert!(b.count_ones() == 1);
-        assert!(c.count_ones() == 1);
-        assert!(d.count_ones() == 1);
-        assert!(e.count_ones() == 1);
-        assert!(f.count_ones() == 1);
-        assert!(g.count_ones() == 1);
-        assert!((a | b | c | d | e | f | g).count_ones() == 7);
-
-        let segment_mapping = [
-            a.trailing_zeros(),
-            b.trailing_zeros(),
-            c.trailing_zeros(),
-            d.trailing_zeros(),
-            e.trailing_zeros(),
-            f.trailing_zeros(),
-            g.trailing_zeros(),
-        ];
-
-        // let mut segment_mapping = [-1; 7];
-        // segment_mapping[a.trailing_zeros() as usize] = 0;
-        // segment_mapping[b.trailing_zeros() as usize] = 1;
-        // segment_mapping[c.trailing_zeros() as usize] = 2;
-        // segment_mapping[d.trailing_zeros() as usize] = 3;
-        // segment_mapping[e.trailing_zeros() as usize] = 4;
-        // segment_mapping[f.trailing_zeros() as usize] = 5;
-        // segment_mapping[g.trailing_zeros() as usize] = 6;
-
-        // TODO: map to original characters
-        let mut mapping = [-1; 10];
-        for i in 0..10 {
-            let original = Digit::original(i);
-            let mut warped = Vec::new();
-            for seg in &original.enabled {
-                warped.push(segment_mapping[*seg] as usize);
-            }
-            warped.sort();
-
-            for p in self.patterns.iter().enumerate() {
-                if p.1.enabled == warped {
-                    mapping[p.0 as usize] = i as i32;
-                }
-            }
+    for &wire in &candidates[wiring.len()] {
+        if found.len() >= limit || wiring.contains(&wire) {
+            continue;
         }
-        for i in 0..10 {
-            assert!(mapping[i] >= 0);
+        wiring.push(wire);
+        let possible = glyphs.iter().all(|g| {
+            let part = warp(*g, wiring);
+            observed
+                .iter()
+                .any(|o| o.count_ones() == g.count_ones() && o & part == part)
+        });
+        if possible {
+            search_wirings(glyphs, observed, candidates, wiring, limit, found);
         }
+        wiring.pop();
+    }
+}
 
-        let mut ret = 0;
-        for i in 0..4 {
-            // find original for this digit
-            let digit = self
-                .patterns
-                .iter()
-                .position(|p| p.enabled == self.output[i].enabled);
-            let digit = digit.expect("please, thanks");
-            ret *= 10;
-            ret += mapping[digit];
+#[derive(Clone, Debug)]
+struct Reading {
+    patterns: Vec<u32>,
+    output: Vec<u32>,
+}
+
+impl Reading {
+    /// Parses the scrambled patterns of all glyphs of `display`, followed
+    /// by the output patterns. Wires are named like the display's segments.
+    fn new(line: &str, display: &Display) -> Result<Self, ParseError> {
+        let (patterns, output) = line.split_once(" | ").ok_or(ParseError::MissingOutput)?;
+        let masks = |part: &str| -> Result<Vec<u32>, ParseError> {
+            part.split(' ')
+                .map(|s| {
+                    display.mask(s).ok_or_else(|| ParseError::UnknownWire {
+                        pattern: String::from(s),
+                    })
+                })
+                .collect()
+        };
+        let patterns = masks(patterns)?;
+        let output = masks(output)?;
+
+        if patterns.len() != display.glyphs.len() {
+            return Err(ParseError::PatternCount {
+                found: patterns.len(),
+            });
         }
 
-        ret
+        Ok(Self { patterns, output })
+    }
+
+    /// The glyph shown by each of the patterns.
+    fn identify(&self, display: &Display) -> Result<Vec<char>, DecodeError> {
+        let wiring = display.wiring(&self.patterns)?;
+        Ok(self
+            .patterns
+            .iter()
+            .map(|p| display.glyph(*p, &wiring).unwrap())
+            .collect())
+    }
+
+    /// The glyphs shown by the output patterns.
+    fn decode(&self, display: &Display) -> Result<Vec<char>, DecodeError> {
+        let wiring = display.wiring(&self.patterns)?;
+        self.output
+            .iter()
+            .enumerate()
+            .map(|(index, p)| {
+                display
+                    .glyph(*p, &wiring)
+                    .ok_or(DecodeError::UnknownOutput { index })
+            })
+            .collect()
+    }
+
+    /// The output read as a decimal number.
+    fn solve(&self) -> Result<i32, DecodeError> {
+        let digits = self.decode(&Display::decimal())?;
+        Ok(digits
+            .iter()
+            .fold(0, |acc, d| acc * 10 + d.to_digit(10).unwrap() as i32))
     }
 }
 
 pub fn solve() {
+    let display = Display::decimal();
     let readings: Vec<Reading> = include_str!("inputs/8.txt")
         .split('\n')
-        .filter_map(|line| Reading::new(line).ok())
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Reading::new(line, &display).expect("invalid input"))
         .collect();
 
     let task1: usize = readings
@@ -201,17 +272,157 @@ pub fn solve() {
             reading
                 .output
                 .iter()
-                .map(|d| match d.count() {
-                    2 | 3 | 4 | 7 => 1,
-                    _ => 0,
-                })
-                .sum::<usize>()
+                .filter(|p| display.unique_length(**p))
+                .count()
         })
         .sum();
 
     println!("[day  8] task 1 = {}", task1);
 
-    let task2: i32 = readings.iter().map(|reading| reading.solve()).sum();
+    // the patterns of every reading show each digit once
+    debug_assert!(readings.iter().all(|reading| {
+        let mut digits = reading.identify(&display).unwrap();
+        digits.sort();
+        digits.iter().collect::<String>() == "0123456789"
+    }));
+
+    let task2: i32 = readings
+        .iter()
+        .map(|reading| reading.solve().expect("undecodable reading"))
+        .sum();
 
     println!("[day  8] task 2 = {}", task2);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodeError, Display, ParseError, Reading, DIGITS};
+
+    const EXAMPLE: &str =
+        "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+
+    #[test]
+    fn test_decode() {
+        let display = Display::decimal();
+        let reading = Reading::new(EXAMPLE, &display).unwrap();
+        assert_eq!(
+            display.wiring(&reading.patterns),
+            Ok(vec![3, 4, 0, 5, 6, 1, 2])
+        );
+        assert_eq!(reading.solve(), Ok(5353));
+
+        // "ab" turned into another "cefabd"
+        let broken = EXAMPLE.replacen("ab |", "cefabd |", 1);
+        let reading = Reading::new(&broken, &display).unwrap();
+        assert_eq!(reading.solve(), Err(DecodeError::Inconsistent));
+
+        let broken = EXAMPLE.replacen("| cdfeb", "| cdf", 1);
+        let reading = Reading::new(&broken, &display).unwrap();
+        assert_eq!(
+            reading.solve(),
+            Err(DecodeError::UnknownOutput { index: 0 })
+        );
+
+        let parse = |line: &str| Reading::new(line, &display).err();
+        assert_eq!(
+            parse(&EXAMPLE.replace(" | ", " ")),
+            Some(ParseError::MissingOutput)
+        );
+        assert_eq!(
+            parse(&EXAMPLE.replacen("dab", "dax", 1)),
+            Some(ParseError::UnknownWire {
+                pattern: String::from("dax")
+            })
+        );
+        assert_eq!(
+            parse(&EXAMPLE.replacen("ab |", "|", 1)),
+            Some(ParseError::PatternCount { found: 9 })
+        );
+        assert_eq!(parse(""), Some(ParseError::MissingOutput));
+    }
+
+    /// Seven segments showing `0` to `9` and `A b C d E F`.
+    fn hex() -> Display {
+        let mut glyphs = DIGITS.to_vec();
+        glyphs.extend([
+            ('A', "abcdef"),
+            ('b', "bdefg"),
+            ('C', "abeg"),
+            ('d', "cdefg"),
+            ('E', "abdeg"),
+            ('F', "abde"),
+        ]);
+        Display::new("abcdefg", &glyphs)
+    }
+
+    /// Scrambles all glyphs of `display` with `wiring`, followed by `output`.
+    fn scrambled(display: &Display, wiring: &[usize], output: &str) -> String {
+        let pattern = |mask: u32| -> String {
+            (0..wiring.len())
+                .filter(|seg| mask & (1 << seg) != 0)
+                .map(|seg| display.segments[wiring[seg]])
+                .collect()
+        };
+        let glyph = |symbol: char| display.glyphs.iter().find(|g| g.0 == symbol).unwrap().1;
+
+        let patterns: Vec<String> = display.glyphs.iter().rev().map(|g| pattern(g.1)).collect();
+        let output: Vec<String> = output.chars().map(|c| pattern(glyph(c))).collect();
+        format!("{} | {}", patterns.join(" "), output.join(" "))
+    }
+
+    #[test]
+    fn test_alphabets() {
+        let hex = hex();
+        let wiring = [6, 2, 4, 0, 1, 5, 3];
+        let reading = Reading::new(&scrambled(&hex, &wiring, "C0FFEE"), &hex).unwrap();
+        assert_eq!(hex.wiring(&reading.patterns), Ok(wiring.to_vec()));
+        assert_eq!(
+            reading.decode(&hex).unwrap().iter().collect::<String>(),
+            "C0FFEE"
+        );
+        let identified: String = reading.identify(&hex).unwrap().iter().collect();
+        assert_eq!(identified, "FEdCbA9876543210");
+
+        // letters on a 14-segment display: a-f are the outer segments going
+        // clockwise from the top, u and v the left and right halves of the
+        // middle bar, h-j the upper diagonal, vertical and diagonal bars
+        // from left to right, k-m the lower ones
+        let letters = Display::new(
+            "abcdefuvhijklm",
+            &[
+                ('A', "abcefuv"),
+                ('B', "abcdilv"),
+                ('C', "adef"),
+                ('D', "abcdil"),
+                ('E', "adefu"),
+                ('F', "aefu"),
+                ('G', "acdefv"),
+                ('H', "bcefuv"),
+                ('I', "adil"),
+                ('J', "bcde"),
+                ('K', "efujm"),
+                ('L', "def"),
+                ('M', "bcefhj"),
+                ('N', "bcefhm"),
+                ('P', "abefuv"),
+                ('R', "abefuvm"),
+                ('T', "ail"),
+                ('U', "bcdef"),
+                ('V', "efkj"),
+                ('W', "bcefkm"),
+                ('X', "hjkm"),
+                ('Y', "hjl"),
+                ('Z', "adjk"),
+            ],
+        );
+        let wiring = [3, 12, 0, 7, 9, 1, 5, 13, 2, 11, 4, 8, 10, 6];
+        let reading = Reading::new(&scrambled(&letters, &wiring, "WAXY"), &letters).unwrap();
+        assert_eq!(letters.wiring(&reading.patterns), Ok(wiring.to_vec()));
+        assert_eq!(reading.decode(&letters).unwrap(), vec!['W', 'A', 'X', 'Y']);
+
+        // mirror images can't be told apart
+        let arrows = Display::new("lrc", &[('<', "lc"), ('>', "rc"), ('|', "c")]);
+        let reading = Reading::new("lc rc c | c", &arrows).unwrap();
+        assert_eq!(reading.decode(&arrows), Err(DecodeError::Ambiguous));
+    }
+}
@@ -0,0 +1,18 @@
+use std::path::PathBuf;
+
+/// The directory given by the `AOC_EXPORT_DIR` environment variable,
+/// if visualizations should be written at all.
+pub fn dir() -> Option<PathBuf> {
+    std::env::var_os("AOC_EXPORT_DIR").map(PathBuf::from)
+}
+
+/// Writes a file named `name` into `dir()`. Does nothing if that isn't
+/// set, so `contents` is only rendered when somebody asked for it.
+pub fn write<F: FnOnce() -> String>(name: &str, contents: F) {
+    if let Some(dir) = dir() {
+        let path = dir.join(name);
+        if let Err(err) = std::fs::write(&path, contents()) {
+            eprintln!("failed to write {}: {}", path.display(), err);
+        }
+    }
+}
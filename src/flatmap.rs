@@ -1,23 +1,107 @@
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::Index;
 use std::slice::Iter;
-use std::slice::IterMut;
 
-pub struct FlatMap<K: Clone + PartialEq, V: Clone> {
+/// How a `FlatMap` finds the position of a key in its entry list.
+pub trait Backend<K>: Default + Clone {
+    /// Position of `key` among `keys`, which are the map's keys in storage order.
+    fn find<'a, I: Iterator<Item = &'a K>>(&self, key: &K, keys: I) -> Option<usize>
+    where
+        K: 'a;
+
+    /// `key` is now stored at `pos`, either because it was added or moved.
+    fn placed(&mut self, key: &K, pos: usize);
+
+    fn removed(&mut self, key: &K);
+}
+
+/// Finds keys by scanning all entries. Lookups cost O(n), but there is no
+/// memory overhead and nothing to hash, which wins for a handful of keys.
+#[derive(Default, Clone)]
+pub struct Linear;
+
+impl<K: PartialEq> Backend<K> for Linear {
+    fn find<'a, I: Iterator<Item = &'a K>>(&self, key: &K, mut keys: I) -> Option<usize>
+    where
+        K: 'a,
+    {
+        keys.position(|k| k == key)
+    }
+
+    fn placed(&mut self, _key: &K, _pos: usize) {}
+
+    fn removed(&mut self, _key: &K) {}
+}
+
+/// Keeps a hash index next to the entries. Lookups cost O(1) on average,
+/// at the price of storing every key twice and hashing it on each access.
+#[derive(Clone)]
+pub struct Hashed<K>(HashMap<K, usize>);
+
+impl<K> Default for Hashed<K> {
+    fn default() -> Self {
+        Self(HashMap::new())
+    }
+}
+
+impl<K: Hash + Eq + Clone> Backend<K> for Hashed<K> {
+    fn find<'a, I: Iterator<Item = &'a K>>(&self, key: &K, _keys: I) -> Option<usize>
+    where
+        K: 'a,
+    {
+        self.0.get(key).copied()
+    }
+
+    fn placed(&mut self, key: &K, pos: usize) {
+        self.0.insert(key.clone(), pos);
+    }
+
+    fn removed(&mut self, key: &K) {
+        self.0.remove(key);
+    }
+}
+
+/// A map storing its entries in a flat list, in insertion order
+/// (until the first `remove`, which moves the last entry into the gap).
+/// The `Linear` default suits small maps; use `HashFlatMap` for many keys.
+#[derive(Clone)]
+pub struct FlatMap<K: Clone + PartialEq, V: Clone, B: Backend<K> = Linear> {
     data: Vec<(K, V)>,
+    backend: B,
 }
 
-impl<K: Clone + PartialEq, V: Clone> FlatMap<K, V> {
+pub type HashFlatMap<K, V> = FlatMap<K, V, Hashed<K>>;
+
+impl<K: Clone + PartialEq, V: Clone, B: Backend<K>> FlatMap<K, V, B> {
     pub fn new() -> Self {
         let data = Vec::new();
-        Self { data }
+        let backend = B::default();
+        Self { data, backend }
     }
 
     fn index(&self, key: &K) -> Option<usize> {
-        self.data
-            .iter()
-            .enumerate()
-            .filter_map(|v| if v.1 .0 == *key { Some(v.0) } else { None })
-            .next()
+        self.backend.find(key, self.data.iter().map(|v| &v.0))
+    }
+
+    fn push(&mut self, key: K, value: V) -> usize {
+        let idx = self.data.len();
+        self.backend.placed(&key, idx);
+        self.data.push((key, value));
+        idx
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.index(key).is_some()
     }
 
     pub fn get(&self, key: &K) -> Option<&V> {
@@ -30,26 +114,47 @@ impl<K: Clone + PartialEq, V: Clone> FlatMap<K, V> {
 
     pub fn set(&mut self, key: &K, value: V) {
         match self.index(key) {
-            None => self.data.push((key.clone(), value)),
+            None => {
+                self.push(key.clone(), value);
+            }
             Some(idx) => self.data[idx].1 = value,
         }
     }
 
     pub fn at(&mut self, key: &K, default: &V) -> &mut V {
         let idx = self.index(key);
-        let idx = idx.unwrap_or_else(|| {
-            self.data.push((key.clone(), default.clone()));
-            self.data.len() - 1
-        });
+        let idx = idx.unwrap_or_else(|| self.push(key.clone(), default.clone()));
         &mut self.data[idx].1
     }
 
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, B> {
+        let idx = self.index(&key);
+        Entry {
+            map: self,
+            key,
+            idx,
+        }
+    }
+
+    /// Removes `key`, moving the last entry into its place.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let idx = self.index(key)?;
+        self.backend.removed(key);
+        let (_, value) = self.data.swap_remove(idx);
+        if let Some(moved) = self.data.get(idx) {
+            self.backend.placed(&moved.0, idx);
+        }
+        Some(value)
+    }
+
     pub fn iter(&self) -> Iter<'_, (K, V)> {
         self.data.iter()
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<'_, (K, V)> {
-        self.data.iter_mut()
+    /// Iterates the entries with mutable values. Keys stay immutable, so
+    /// the backend's index can't go stale.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> + '_ {
+        self.data.iter_mut().map(|(k, v)| (&*k, v))
     }
 
     pub fn data(&self) -> &Vec<(K, V)> {
@@ -61,8 +166,121 @@ impl<K: Clone + PartialEq, V: Clone> FlatMap<K, V> {
     }
 }
 
-impl<K: Clone + PartialEq + Debug, V: Clone + Debug> Debug for FlatMap<K, V> {
+/// A key of a `FlatMap` that may or may not be present yet.
+pub struct Entry<'a, K: Clone + PartialEq, V: Clone, B: Backend<K>> {
+    map: &'a mut FlatMap<K, V, B>,
+    key: K,
+    idx: Option<usize>,
+}
+
+impl<'a, K: Clone + PartialEq, V: Clone, B: Backend<K>> Entry<'a, K, V, B> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        if let Some(idx) = self.idx {
+            f(&mut self.map.data[idx].1);
+        }
+        self
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, f: F) -> &'a mut V {
+        let idx = match self.idx {
+            Some(idx) => idx,
+            None => self.map.push(self.key, f()),
+        };
+        &mut self.map.data[idx].1
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<K: Clone + PartialEq, V: Clone, B: Backend<K>> Default for FlatMap<K, V, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Clone + PartialEq, V: Clone, B: Backend<K>> Index<&K> for FlatMap<K, V, B> {
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: Clone + PartialEq, V: Clone, B: Backend<K>> Extend<(K, V)> for FlatMap<K, V, B> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.set(&key, value);
+        }
+    }
+}
+
+impl<K: Clone + PartialEq, V: Clone, B: Backend<K>> FromIterator<(K, V)> for FlatMap<K, V, B> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut ret = Self::new();
+        ret.extend(iter);
+        ret
+    }
+}
+
+impl<K: Clone + PartialEq + Debug, V: Clone + Debug, B: Backend<K>> Debug for FlatMap<K, V, B> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_list().entries(&self.data).finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Backend, FlatMap, HashFlatMap};
+
+    fn exercise<B: Backend<u32>>(map: &mut FlatMap<u32, u32, B>) {
+        assert_eq!(map.len(), 10);
+        assert_eq!(map[&3], 9);
+
+        assert_eq!(map.remove(&3), Some(9));
+        assert_eq!(map.remove(&3), None);
+        assert!(!map.contains_key(&3));
+        assert_eq!(map[&9], 81);
+        assert_eq!(map.len(), 9);
+
+        *map.entry(9).or_insert(0) += 1;
+        *map.entry(3).or_insert(0) += 1;
+        map.entry(4).and_modify(|v| *v = 0).or_default();
+        map.extend([(5, 55), (20, 400)]);
+
+        assert_eq!(map[&9], 82);
+        assert_eq!(map[&3], 1);
+        assert_eq!(map[&4], 0);
+        assert_eq!(map[&5], 55);
+        assert_eq!(map.get(&20), Some(&400));
+        assert_eq!(map.len(), 11);
+
+        for (k, v) in map.iter_mut() {
+            *v += k;
+        }
+        assert_eq!(map.get(&20), Some(&420));
+        assert_eq!(map.remove(&5), Some(60));
+    }
+
+    #[test]
+    fn test_backends_agree() {
+        let mut linear: FlatMap<u32, u32> = (0..10).map(|k| (k, k * k)).collect();
+        let mut hashed: HashFlatMap<u32, u32> = (0..10).map(|k| (k, k * k)).collect();
+
+        exercise(&mut linear);
+        exercise(&mut hashed);
+        assert_eq!(linear.data(), hashed.data());
+    }
+}
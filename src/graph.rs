@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+
+/// A node of a `Graph`, with the indices of the nodes it is connected to.
+pub struct Node<L> {
+    pub label: L,
+    pub edges: Vec<usize>,
+}
+
+/// An undirected graph with labelled nodes, stored as adjacency lists.
+pub struct Graph<L> {
+    nodes: Vec<Node<L>>,
+}
+
+/// Result of `Graph::count_paths_memoized`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PathStats {
+    pub paths: usize,
+    /// Nodes visited while walking all paths one by one, counting every
+    /// step into a node, including dead ends.
+    pub visited: usize,
+}
+
+/// Decides which nodes a path may enter, see `Graph::for_each_path`.
+pub trait VisitPolicy<L> {
+    /// Whatever the policy needs to remember about a path so far,
+    /// e.g. whether a one-time extra visit was used up already.
+    type State: Copy + Default;
+
+    /// Whether a path may enter `node` again after already visiting it
+    /// `visits` times. Returns the state of the extended path if so.
+    fn enter(
+        &self,
+        graph: &Graph<L>,
+        node: usize,
+        visits: usize,
+        state: Self::State,
+    ) -> Option<Self::State>;
+
+    /// Whether a path that reached its end with `state` counts.
+    fn accept(&self, _state: Self::State) -> bool {
+        true
+    }
+}
+
+/// Nodes matching `small` may be visited at most `k` times, all others
+/// as often as one likes.
+pub struct SmallAtMost<F> {
+    pub small: F,
+    pub k: usize,
+}
+
+impl<L, F: Fn(&L) -> bool> VisitPolicy<L> for SmallAtMost<F> {
+    type State = ();
+
+    fn enter(&self, graph: &Graph<L>, node: usize, visits: usize, _state: ()) -> Option<()> {
+        if visits < self.k || !(self.small)(&graph.node(node).label) {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+/// Nodes matching `small` may be visited once, except for a single one
+/// which may be visited twice. If `required`, only paths that actually
+/// visit some small node twice are accepted.
+pub struct OneSmallTwice<F> {
+    pub small: F,
+    pub required: bool,
+}
+
+impl<L, F: Fn(&L) -> bool> VisitPolicy<L> for OneSmallTwice<F> {
+    type State = bool;
+
+    fn enter(&self, graph: &Graph<L>, node: usize, visits: usize, twice: bool) -> Option<bool> {
+        if visits == 0 || !(self.small)(&graph.node(node).label) {
+            Some(twice)
+        } else if visits == 1 && !twice {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    fn accept(&self, twice: bool) -> bool {
+        twice || !self.required
+    }
+}
+
+/// Every node may be visited as often as its entry in the budget says.
+pub struct Budget(pub Vec<usize>);
+
+impl<L> VisitPolicy<L> for Budget {
+    type State = ();
+
+    fn enter(&self, _graph: &Graph<L>, node: usize, visits: usize, _state: ()) -> Option<()> {
+        if visits < self.0[node] {
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
+impl<L> Graph<L> {
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn add_node(&mut self, label: L) -> usize {
+        self.nodes.push(Node {
+            label,
+            edges: Vec::new(),
+        });
+        self.nodes.len() - 1
+    }
+
+    pub fn add_edge(&mut self, a: usize, b: usize) {
+        self.nodes[a].edges.push(b);
+        self.nodes[b].edges.push(a);
+    }
+
+    pub fn has_edge(&self, a: usize, b: usize) -> bool {
+        self.nodes[a].edges.contains(&b)
+    }
+
+    pub fn node(&self, idx: usize) -> &Node<L> {
+        &self.nodes[idx]
+    }
+
+    pub fn nodes(&self) -> &Vec<Node<L>> {
+        &self.nodes
+    }
+
+    /// Index of the first node whose label matches.
+    pub fn find<F: Fn(&L) -> bool>(&self, pred: F) -> Option<usize> {
+        self.nodes.iter().position(|n| pred(&n.label))
+    }
+
+    /// Renders the graph in Graphviz DOT format. `node_attrs` and `edge_attrs`
+    /// return the attribute list of a node or of the edge between two nodes,
+    /// e.g. `label="a", color=red`. Every edge is written once, from the
+    /// lower to the higher index.
+    pub fn to_dot<N, E>(&self, mut node_attrs: N, mut edge_attrs: E) -> String
+    where
+        N: FnMut(usize, &L) -> String,
+        E: FnMut(usize, usize) -> String,
+    {
+        let with_attrs = |item: String, attrs: String| {
+            if attrs.is_empty() {
+                format!("    {};\n", item)
+            } else {
+                format!("    {} [{}];\n", item, attrs)
+            }
+        };
+
+        let mut ret = String::from("graph {\n");
+        for (idx, node) in self.nodes.iter().enumerate() {
+            ret += &with_attrs(idx.to_string(), node_attrs(idx, &node.label));
+        }
+        for (a, node) in self.nodes.iter().enumerate() {
+            for &b in node.edges.iter().filter(|b| **b > a) {
+                ret += &with_attrs(format!("{} -- {}", a, b), edge_attrs(a, b));
+            }
+        }
+        ret += "}\n";
+        ret
+    }
+
+    /// Calls `f` with every path from `from` to `to` that `policy` allows.
+    /// Paths never return to `from` and stop as soon as they reach `to`.
+    pub fn for_each_path<P, F>(&self, from: usize, to: usize, policy: &P, mut f: F)
+    where
+        P: VisitPolicy<L>,
+        F: FnMut(&[usize]),
+    {
+        let mut path = vec![from];
+        let mut visits = vec![0; self.nodes.len()];
+        visits[from] = 1;
+        self.extend_path(
+            to,
+            policy,
+            P::State::default(),
+            &mut path,
+            &mut visits,
+            &mut f,
+        );
+    }
+
+    fn extend_path<P, F>(
+        &self,
+        to: usize,
+        policy: &P,
+        state: P::State,
+        path: &mut Vec<usize>,
+        visits: &mut Vec<usize>,
+        f: &mut F,
+    ) where
+        P: VisitPolicy<L>,
+        F: FnMut(&[usize]),
+    {
+        let curr = *path.last().unwrap();
+        if curr == to {
+            if policy.accept(state) {
+                f(path);
+            }
+            return;
+        }
+
+        for &next in &self.nodes[curr].edges {
+            if next == path[0] {
+                continue;
+            }
+            if let Some(state) = policy.enter(self, next, visits[next], state) {
+                visits[next] += 1;
+                path.push(next);
+                self.extend_path(to, policy, state, path, visits, f);
+                path.pop();
+                visits[next] -= 1;
+            }
+        }
+    }
+
+    /// All paths from `from` to `to` that `policy` allows, as node indices.
+    pub fn paths<P: VisitPolicy<L>>(&self, from: usize, to: usize, policy: &P) -> Vec<Vec<usize>> {
+        let mut ret = Vec::new();
+        self.for_each_path(from, to, policy, |path| ret.push(path.to_vec()));
+        ret
+    }
+
+    pub fn count_paths<P: VisitPolicy<L>>(&self, from: usize, to: usize, policy: &P) -> usize {
+        let mut ret = 0;
+        self.for_each_path(from, to, policy, |_| ret += 1);
+        ret
+    }
+
+    /// Counts the paths from `from` to `to` that visit nodes matching `small`
+    /// at most once, except for a single one that may be visited twice if
+    /// `extra_visit` is set. Other nodes may be visited any number of times,
+    /// so they must not be adjacent to each other.
+    ///
+    /// Instead of walking every path, this caches the result for each
+    /// (node, visited small nodes, extra visit used) state.
+    pub fn count_paths_memoized<F: Fn(&L) -> bool>(
+        &self,
+        from: usize,
+        to: usize,
+        small: F,
+        extra_visit: bool,
+    ) -> PathStats {
+        let mut bits = vec![None; self.nodes.len()];
+        let mut next_bit = 0;
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if small(&node.label) {
+                assert!(next_bit < 64, "too many small nodes");
+                bits[idx] = Some(next_bit);
+                next_bit += 1;
+            }
+        }
+
+        let mut cache = HashMap::new();
+        self.count_from(from, to, &bits, (from, 0, !extra_visit), &mut cache)
+    }
+
+    fn count_from(
+        &self,
+        from: usize,
+        to: usize,
+        bits: &[Option<u32>],
+        state: (usize, u64, bool),
+        cache: &mut HashMap<(usize, u64, bool), PathStats>,
+    ) -> PathStats {
+        let (curr, visited, extra_used) = state;
+        if curr == to {
+            return PathStats {
+                paths: 1,
+                visited: 1,
+            };
+        }
+        if let Some(stats) = cache.get(&state) {
+            return *stats;
+        }
+
+        let mut ret = PathStats {
+            paths: 0,
+            visited: 1,
+        };
+        for &next in &self.nodes[curr].edges {
+            if next == from {
+                continue;
+            }
+            let next_state = match bits[next] {
+                None => (next, visited, extra_used),
+                Some(bit) if visited & (1 << bit) == 0 => (next, visited | (1 << bit), extra_used),
+                Some(_) if !extra_used => (next, visited, true),
+                Some(_) => continue,
+            };
+            let stats = self.count_from(from, to, bits, next_state, cache);
+            ret.paths += stats.paths;
+            ret.visited += stats.visited;
+        }
+
+        cache.insert(state, ret);
+        ret
+    }
+}
+
+impl<L> Default for Graph<L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Budget, Graph, OneSmallTwice, PathStats, SmallAtMost};
+
+    #[test]
+    fn test_policies() {
+        // a - b - c with a loop b - x - c
+        let mut graph = Graph::new();
+        let a = graph.add_node('a');
+        let b = graph.add_node('b');
+        let c = graph.add_node('c');
+        let x = graph.add_node('X');
+        graph.add_edge(a, b);
+        graph.add_edge(b, c);
+        graph.add_edge(b, x);
+        graph.add_edge(x, c);
+
+        let small = |l: &char| l.is_lowercase();
+        let once = SmallAtMost { small, k: 1 };
+        let mut paths = graph.paths(a, c, &once);
+        paths.sort();
+        assert_eq!(paths, vec![vec![a, b, c], vec![a, b, x, c]]);
+
+        // additionally b-x-b-c and b-x-b-x-c
+        let twice = OneSmallTwice {
+            small,
+            required: false,
+        };
+        assert_eq!(graph.count_paths(a, c, &twice), 4);
+        let exactly = OneSmallTwice {
+            small,
+            required: true,
+        };
+        assert_eq!(graph.count_paths(a, c, &exactly), 2);
+
+        let memo = graph.count_paths_memoized(a, c, small, true);
+        assert_eq!(memo.paths, 4);
+        let memo = graph.count_paths_memoized(a, c, small, false);
+        assert_eq!(
+            memo,
+            PathStats {
+                paths: 2,
+                visited: 5
+            }
+        );
+
+        let dot = graph.to_dot(
+            |_, l| format!("label=\"{}\"", l),
+            |a, b| {
+                if (a, b) == (1, 2) {
+                    "color=red".into()
+                } else {
+                    String::new()
+                }
+            },
+        );
+        assert_eq!(
+            dot,
+            "graph {\n    0 [label=\"a\"];\n    1 [label=\"b\"];\n    2 [label=\"c\"];\n    \
+             3 [label=\"X\"];\n    0 -- 1;\n    1 -- 2 [color=red];\n    1 -- 3;\n    2 -- 3;\n}\n"
+        );
+
+        let budget = Budget(vec![1, 1, 1, 0]);
+        assert_eq!(graph.paths(a, c, &budget), vec![vec![a, b, c]]);
+    }
+}
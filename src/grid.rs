@@ -0,0 +1,42 @@
+/// Cell count up to which a dense grid is preferred over a sparse one.
+pub const DENSE_CELL_LIMIT: usize = 1 << 24;
+
+pub fn prefer_dense(width: usize, height: usize) -> bool {
+    width
+        .checked_mul(height)
+        .is_some_and(|cells| cells <= DENSE_CELL_LIMIT)
+}
+
+/// Fixed-size grids of small values, so that callers can pick dense or
+/// sparse storage depending on the input.
+/// Cells that are not explicitly set hold the grid's background,
+/// `T::default()` unless the grid says otherwise.
+pub trait Grid<T: Copy + Default + PartialEq>: Sized {
+    fn new(width: usize, height: usize) -> Self;
+
+    fn width(&self) -> usize;
+
+    fn height(&self) -> usize;
+
+    fn value(&self, pos: (usize, usize)) -> T;
+
+    fn set(&mut self, pos: (usize, usize), value: T);
+
+    /// The value of cells that were never set.
+    fn background(&self) -> T {
+        T::default()
+    }
+
+    /// All cells that hold something other than the background.
+    /// Dense grids yield them row by row, sparse grids in no particular order.
+    fn occupied(&self) -> impl Iterator<Item = ((usize, usize), T)> + '_;
+
+    fn render<F: FnMut(T) -> char>(&self, mut cell: F) -> String {
+        let mut ret = String::new();
+        for y in 0..self.height() {
+            ret.extend((0..self.width()).map(|x| cell(self.value((x, y)))));
+            ret.push('\n');
+        }
+        ret
+    }
+}
@@ -1,4 +1,8 @@
 mod array2d;
+mod arraynd;
+mod bitgrid;
+mod brackets;
+mod counter;
 mod day1;
 mod day10;
 mod day11;
@@ -24,7 +28,14 @@ mod day6;
 mod day7;
 mod day8;
 mod day9;
+mod export;
 mod flatmap;
+mod graph;
+mod grid;
+mod netpbm;
+mod recorder;
+mod sparsegrid;
+mod unboundedgrid;
 
 fn main() {
     day1::solve();
@@ -0,0 +1,100 @@
+use crate::array2d::Array2D;
+
+// plain Netpbm files should not have lines longer than 70 characters
+const MAX_LINE: usize = 70;
+
+/// Writes the samples of a plain (ASCII) Netpbm image after `header`,
+/// turning every cell into a `scale` x `scale` block of pixels.
+fn encode<T, F, const N: usize>(
+    header: &str,
+    array: &Array2D<T>,
+    scale: usize,
+    mut pixel: F,
+) -> String
+where
+    T: Default + Clone,
+    F: FnMut(&T) -> [u8; N],
+{
+    let mut ret = String::from(header);
+    for y in 0..array.height() {
+        let row: Vec<[u8; N]> = array.row(y).iter().map(&mut pixel).collect();
+        for _ in 0..scale {
+            let mut line_len = 0;
+            for samples in &row {
+                for _ in 0..scale {
+                    for sample in samples {
+                        let token = sample.to_string();
+                        if line_len > 0 && line_len + 1 + token.len() > MAX_LINE {
+                            ret.push('\n');
+                            line_len = 0;
+                        } else if line_len > 0 {
+                            ret.push(' ');
+                            line_len += 1;
+                        }
+                        line_len += token.len();
+                        ret.push_str(&token);
+                    }
+                }
+            }
+            ret.push('\n');
+        }
+    }
+    ret
+}
+
+impl<T: Default + Clone> Array2D<T> {
+    /// Renders a black and white PBM (P1) image; cells mapping to `true` are black.
+    pub fn to_pbm<F: FnMut(&T) -> bool>(&self, scale: usize, mut black: F) -> String {
+        let header = format!("P1\n{} {}\n", self.width() * scale, self.height() * scale);
+        encode(&header, self, scale, |v| [black(v) as u8])
+    }
+
+    /// Renders a grayscale PGM (P2) image with gray levels from 0 (black) to 255.
+    pub fn to_pgm<F: FnMut(&T) -> u8>(&self, scale: usize, mut gray: F) -> String {
+        let header = format!(
+            "P2\n{} {}\n255\n",
+            self.width() * scale,
+            self.height() * scale
+        );
+        encode(&header, self, scale, |v| [gray(v)])
+    }
+
+    /// Renders a color PPM (P3) image from `[red, green, blue]` triples.
+    pub fn to_ppm<F: FnMut(&T) -> [u8; 3]>(&self, scale: usize, rgb: F) -> String {
+        let header = format!(
+            "P3\n{} {}\n255\n",
+            self.width() * scale,
+            self.height() * scale
+        );
+        encode(&header, self, scale, rgb)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::array2d::Array2D;
+
+    #[test]
+    fn test_formats() {
+        let grid = Array2D::parse_grid("#.\n.#\n", |c| Ok::<bool, ()>(c == '#')).unwrap();
+
+        assert_eq!(grid.to_pbm(1, |b| *b), "P1\n2 2\n1 0\n0 1\n");
+        assert_eq!(
+            grid.to_pbm(2, |b| *b),
+            "P1\n4 4\n1 1 0 0\n1 1 0 0\n0 0 1 1\n0 0 1 1\n"
+        );
+        assert_eq!(
+            grid.to_pgm(1, |b| if *b { 0 } else { 255 }),
+            "P2\n2 2\n255\n0 255\n255 0\n"
+        );
+        assert_eq!(
+            grid.to_ppm(1, |b| if *b { [255, 0, 0] } else { [0, 0, 0] }),
+            "P3\n2 2\n255\n255 0 0 0 0 0\n0 0 0 255 0 0\n"
+        );
+
+        let wide = Array2D::<u8>::new(40, 1);
+        let pgm = wide.to_pgm(1, |_| 100);
+        assert!(pgm.lines().all(|line| line.len() <= 70));
+        assert_eq!(pgm.split_whitespace().count(), 4 + 40);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::array2d::Array2D;
+use crate::export;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+type Render<T> = Box<dyn Fn(&Array2D<T>) -> String>;
+
+/// Captures rendered frames of a grid simulation, one per recorded step.
+pub struct Recorder<T: Default + Clone> {
+    render: Render<T>,
+    steps: Range<usize>,
+    stride: usize,
+    frames: Vec<(usize, String)>,
+}
+
+impl<T: Default + Clone> Recorder<T> {
+    /// Records every step, turning each grid into a frame with `render`.
+    pub fn new<F: Fn(&Array2D<T>) -> String + 'static>(render: F) -> Self {
+        Self {
+            render: Box::new(render),
+            steps: 0..usize::MAX,
+            stride: 1,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Only records steps within `steps`.
+    pub fn steps(mut self, steps: Range<usize>) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Only records every `stride`-th step, counted from the start of the range.
+    pub fn stride(mut self, stride: usize) -> Self {
+        assert!(stride > 0);
+        self.stride = stride;
+        self
+    }
+
+    /// Whether `capture` would record `step`, so callers can skip preparing the grid.
+    pub fn wants(&self, step: usize) -> bool {
+        self.steps.contains(&step) && (step - self.steps.start).is_multiple_of(self.stride)
+    }
+
+    pub fn capture(&mut self, step: usize, grid: &Array2D<T>) {
+        if self.wants(step) {
+            let frame = (self.render)(grid);
+            self.frames.push((step, frame));
+        }
+    }
+
+    pub fn frames(&self) -> &[(usize, String)] {
+        &self.frames
+    }
+
+    /// Writes every frame to its own file `<prefix><step>.<extension>` in `dir`,
+    /// with zero-padded step numbers so the files sort in order.
+    pub fn write_frames(&self, dir: &Path, prefix: &str, extension: &str) -> io::Result<()> {
+        let digits = self
+            .frames()
+            .last()
+            .map_or(1, |(step, _)| step.to_string().len());
+        for (step, frame) in self.frames() {
+            let name = format!("{}{:0width$}.{}", prefix, step, extension, width = digits);
+            std::fs::write(dir.join(name), frame)?;
+        }
+        Ok(())
+    }
+
+    /// Concatenates all frames into one text, each one preceded by a line
+    /// holding `delimiter` and the step number.
+    pub fn to_animation(&self, delimiter: &str) -> String {
+        let mut ret = String::new();
+        for (step, frame) in self.frames() {
+            ret.push_str(&format!("{} {}\n", delimiter, step));
+            ret.push_str(frame);
+            if !frame.ends_with('\n') {
+                ret.push('\n');
+            }
+        }
+        ret
+    }
+
+    /// Writes the text animation to `name` in `export::dir()`, if it is set.
+    pub fn export_animation(&self, name: &str, delimiter: &str) {
+        export::write(name, || self.to_animation(delimiter));
+    }
+
+    /// Writes the frames into `export::dir()`, if it is set.
+    pub fn export(&self, prefix: &str, extension: &str) {
+        if let Some(dir) = export::dir() {
+            if let Err(err) = self.write_frames(&dir, prefix, extension) {
+                eprintln!("failed to write {} frames: {}", prefix, err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Recorder;
+    use crate::array2d::Array2D;
+
+    #[test]
+    fn test_range_and_stride() {
+        let mut recorder = Recorder::new(|g: &Array2D<u32>| {
+            g.to_grid_string(|v| char::from_digit(*v, 10).unwrap())
+        })
+        .steps(2..9)
+        .stride(3);
+
+        let mut grid = Array2D::new(2, 1);
+        for step in 0..12 {
+            grid.fill(step as u32 % 10);
+            recorder.capture(step, &grid);
+        }
+
+        let steps: Vec<usize> = recorder.frames().iter().map(|f| f.0).collect();
+        assert_eq!(steps, vec![2, 5, 8]);
+        assert_eq!(
+            recorder.to_animation("--"),
+            "-- 2\n22\n-- 5\n55\n-- 8\n88\n"
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use crate::grid::Grid;
+use std::collections::HashMap;
+
+/// A fixed-size grid that only stores cells differing from its background,
+/// `T::default()` until the next `fill`, for coordinate spaces too large to
+/// allocate densely.
+#[derive(Clone)]
+pub struct SparseGrid<T: Default + Clone + PartialEq> {
+    width: usize,
+    height: usize,
+    background: T,
+    cells: HashMap<(usize, usize), T>,
+}
+
+impl<T: Default + Clone + PartialEq> SparseGrid<T> {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            background: T::default(),
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The value of every cell that is not stored.
+    pub fn background(&self) -> &T {
+        &self.background
+    }
+
+    /// Sets every cell to `value`, dropping all stored cells.
+    pub fn fill(&mut self, value: T) {
+        self.cells.clear();
+        self.background = value;
+    }
+
+    pub fn at(&self, pos: (usize, usize)) -> &T {
+        debug_assert!(pos.0 < self.width);
+        debug_assert!(pos.1 < self.height);
+        self.cells.get(&pos).unwrap_or(&self.background)
+    }
+
+    pub fn at_mut(&mut self, pos: (usize, usize)) -> &mut T {
+        assert!(pos.0 < self.width);
+        assert!(pos.1 < self.height);
+        self.cells
+            .entry(pos)
+            .or_insert_with(|| self.background.clone())
+    }
+
+    pub fn set(&mut self, pos: (usize, usize), value: T) {
+        if value == self.background {
+            self.cells.remove(&pos);
+        } else {
+            *self.at_mut(pos) = value;
+        }
+    }
+
+    /// Like `at`, but returns `None` for positions outside the grid.
+    pub fn get(&self, pos: (isize, isize)) -> Option<&T> {
+        if pos.0 < 0 || pos.1 < 0 {
+            return None;
+        }
+        let pos = (pos.0 as usize, pos.1 as usize);
+        if pos.0 < self.width && pos.1 < self.height {
+            Some(self.at(pos))
+        } else {
+            None
+        }
+    }
+
+    /// Number of stored cells.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Iterates the values of the stored cells in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.cells.values()
+    }
+
+    /// Iterates the stored cells in no particular order.
+    pub fn indexed_iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> + '_ {
+        self.cells.iter().map(|(pos, v)| (*pos, v))
+    }
+
+    /// Inclusive minimum and maximum coordinates of the stored cells.
+    pub fn bounding_box(&self) -> Option<((usize, usize), (usize, usize))> {
+        self.cells.keys().fold(None, |bb, pos| {
+            let (min, max) = bb.unwrap_or((*pos, *pos));
+            Some((
+                (min.0.min(pos.0), min.1.min(pos.1)),
+                (max.0.max(pos.0), max.1.max(pos.1)),
+            ))
+        })
+    }
+
+    /// Renders the grid, one line per row and one character per cell.
+    pub fn to_grid_string<F: FnMut(&T) -> char>(&self, mut cell: F) -> String {
+        let mut ret = String::new();
+        for y in 0..self.height {
+            ret.extend((0..self.width).map(|x| cell(self.at((x, y)))));
+            ret.push('\n');
+        }
+        ret
+    }
+}
+
+impl<T: Copy + Default + PartialEq> Grid<T> for SparseGrid<T> {
+    fn new(width: usize, height: usize) -> Self {
+        SparseGrid::new(width, height)
+    }
+
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn value(&self, pos: (usize, usize)) -> T {
+        *self.at(pos)
+    }
+
+    fn set(&mut self, pos: (usize, usize), value: T) {
+        SparseGrid::set(self, pos, value)
+    }
+
+    fn background(&self) -> T {
+        self.background
+    }
+
+    fn occupied(&self) -> impl Iterator<Item = ((usize, usize), T)> + '_ {
+        // `at_mut` may leave a cell equal to the background behind
+        self.cells
+            .iter()
+            .filter(|(_, v)| **v != self.background)
+            .map(|(pos, v)| (*pos, *v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SparseGrid;
+    use crate::grid::Grid;
+
+    #[test]
+    fn test_sparse() {
+        let mut grid = SparseGrid::new(3, 2);
+        grid.set((1, 0), 5);
+        grid.set((2, 1), 7);
+        grid.set((2, 1), 0);
+        assert_eq!(grid.len(), 1);
+        assert_eq!(grid.iter().collect::<Vec<_>>(), vec![&5]);
+        assert_eq!(grid.indexed_iter().collect::<Vec<_>>(), vec![((1, 0), &5)]);
+        assert_eq!(grid.bounding_box(), Some(((1, 0), (1, 0))));
+        assert_eq!(
+            grid.to_grid_string(|v| char::from_digit(*v, 10).unwrap()),
+            "050\n000\n"
+        );
+
+        grid.fill(1);
+        assert!(grid.is_empty());
+        grid.set((0, 1), 0);
+        assert_eq!(
+            grid.to_grid_string(|v| char::from_digit(*v, 10).unwrap()),
+            "111\n011\n"
+        );
+        assert_eq!(Grid::background(&grid), 1);
+        assert_eq!(grid.occupied().collect::<Vec<_>>(), vec![((0, 1), 0)]);
+    }
+}
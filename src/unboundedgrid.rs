@@ -0,0 +1,438 @@
+use crate::array2d::Array2D;
+use crate::bitgrid::BitGrid;
+use crate::grid::Grid;
+
+/// A grid over all of `(i64, i64)`, holding `background` everywhere except
+/// where cells have been written. Storage grows to cover written cells.
+#[derive(Clone)]
+pub struct UnboundedGrid<T: Default + Clone> {
+    origin: (i64, i64),
+    cells: Array2D<T>,
+    bounds: Option<((i64, i64), (i64, i64))>,
+    background: T,
+    size: (usize, usize),
+}
+
+impl<T: Default + Clone> UnboundedGrid<T> {
+    pub fn new(background: T) -> Self {
+        Self {
+            origin: (0, 0),
+            cells: Array2D::new(0, 0),
+            bounds: None,
+            background,
+            size: (0, 0),
+        }
+    }
+
+    /// Places `cells` with its top-left corner at `(0, 0)`.
+    pub fn from_array(cells: Array2D<T>, background: T) -> Self {
+        let bounds = if cells.width() > 0 && cells.height() > 0 {
+            let max = (cells.width() as i64 - 1, cells.height() as i64 - 1);
+            Some(((0, 0), max))
+        } else {
+            None
+        };
+        let size = (cells.width(), cells.height());
+        Self {
+            origin: (0, 0),
+            cells,
+            bounds,
+            background,
+            size,
+        }
+    }
+
+    pub fn background(&self) -> &T {
+        &self.background
+    }
+
+    /// Inclusive minimum and maximum coordinates of all written cells,
+    /// or `None` if nothing was written yet.
+    pub fn bounds(&self) -> Option<((i64, i64), (i64, i64))> {
+        self.bounds
+    }
+
+    fn contains(&self, pos: (i64, i64)) -> bool {
+        match self.bounds {
+            None => false,
+            Some((min, max)) => {
+                pos.0 >= min.0 && pos.1 >= min.1 && pos.0 <= max.0 && pos.1 <= max.1
+            }
+        }
+    }
+
+    fn local(&self, pos: (i64, i64)) -> (usize, usize) {
+        (
+            (pos.0 - self.origin.0) as usize,
+            (pos.1 - self.origin.1) as usize,
+        )
+    }
+
+    pub fn get(&self, pos: (i64, i64)) -> &T {
+        if self.contains(pos) {
+            self.cells.at(self.local(pos))
+        } else {
+            &self.background
+        }
+    }
+
+    pub fn at_mut(&mut self, pos: (i64, i64)) -> &mut T {
+        self.reserve(pos);
+        self.bounds = Some(match self.bounds {
+            None => (pos, pos),
+            Some((min, max)) => (
+                (min.0.min(pos.0), min.1.min(pos.1)),
+                (max.0.max(pos.0), max.1.max(pos.1)),
+            ),
+        });
+        let pos = self.local(pos);
+        self.cells.at_mut(pos)
+    }
+
+    pub fn set(&mut self, pos: (i64, i64), value: T) {
+        *self.at_mut(pos) = value;
+    }
+
+    /// Iterates all cells within `bounds`, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = ((i64, i64), &T)> + '_ {
+        let (min, max) = self.bounds.unwrap_or(((0, 0), (-1, -1)));
+        (min.1..=max.1)
+            .flat_map(move |y| (min.0..=max.0).map(move |x| (x, y)))
+            .map(move |pos| (pos, self.get(pos)))
+    }
+
+    /// Copies the cells within `bounds` into a dense array, so that its
+    /// top-left corner holds the cell at the minimum coordinates.
+    pub fn to_array2d(&self) -> Array2D<T> {
+        match self.bounds {
+            None => Array2D::new(0, 0),
+            Some((min, max)) => {
+                let (x, y) = self.local(min);
+                let w = (max.0 - min.0 + 1) as usize;
+                let h = (max.1 - min.1 + 1) as usize;
+                self.cells.cropped((x, y), w, h)
+            }
+        }
+    }
+
+    /// Makes sure `pos` is backed by storage, growing it with some slack
+    /// so that a grid growing one cell at a time doesn't copy every time.
+    fn reserve(&mut self, pos: (i64, i64)) {
+        let w = self.cells.width() as i64;
+        let h = self.cells.height() as i64;
+        let (x, y) = (pos.0 - self.origin.0, pos.1 - self.origin.1);
+        if x >= 0 && y >= 0 && x < w && y < h {
+            return;
+        }
+
+        let slack_x = (w / 2).max(4);
+        let slack_y = (h / 2).max(4);
+        let (mut x0, mut y0, mut x1, mut y1) = if w > 0 && h > 0 {
+            (0, 0, w, h)
+        } else {
+            (x, y, x + 1, y + 1)
+        };
+        if x < x0 {
+            x0 = x - slack_x;
+        }
+        if y < y0 {
+            y0 = y - slack_y;
+        }
+        if x >= x1 {
+            x1 = x + 1 + slack_x;
+        }
+        if y >= y1 {
+            y1 = y + 1 + slack_y;
+        }
+
+        let mut cells = Array2D::new((x1 - x0) as usize, (y1 - y0) as usize);
+        cells.fill(self.background.clone());
+        if w > 0 && h > 0 {
+            cells.blit(&self.cells, ((-x0) as usize, (-y0) as usize));
+        }
+        self.origin = (self.origin.0 + x0, self.origin.1 + y0);
+        self.cells = cells;
+    }
+}
+
+impl<T: Copy + Default + PartialEq> Grid<T> for UnboundedGrid<T> {
+    /// Reserves storage for `width` x `height` cells at the origin, with
+    /// `T::default()` as background. The grid still grows when needed.
+    fn new(width: usize, height: usize) -> Self {
+        let mut ret = UnboundedGrid::new(T::default());
+        ret.cells = Array2D::new(width, height);
+        ret.size = (width, height);
+        ret
+    }
+
+    /// The size requested in `new`, however far the grid has grown since.
+    fn width(&self) -> usize {
+        self.size.0
+    }
+
+    fn height(&self) -> usize {
+        self.size.1
+    }
+
+    fn value(&self, pos: (usize, usize)) -> T {
+        *self.get((pos.0 as i64, pos.1 as i64))
+    }
+
+    fn set(&mut self, pos: (usize, usize), value: T) {
+        UnboundedGrid::set(self, (pos.0 as i64, pos.1 as i64), value)
+    }
+
+    fn background(&self) -> T {
+        self.background
+    }
+
+    fn occupied(&self) -> impl Iterator<Item = ((usize, usize), T)> + '_ {
+        self.iter()
+            .filter(|(pos, v)| pos.0 >= 0 && pos.1 >= 0 && **v != self.background)
+            .map(|(pos, v)| ((pos.0 as usize, pos.1 as usize), *v))
+    }
+}
+
+/// The boolean counterpart of `UnboundedGrid`, packed into a `BitGrid` so
+/// that rows can be processed a word at a time.
+#[derive(Clone)]
+pub struct UnboundedBitGrid {
+    origin: (i64, i64),
+    bits: BitGrid,
+    bounds: Option<((i64, i64), (i64, i64))>,
+    background: bool,
+}
+
+impl UnboundedBitGrid {
+    pub fn new(background: bool) -> Self {
+        Self {
+            origin: (0, 0),
+            bits: BitGrid::new(0, 0),
+            bounds: None,
+            background,
+        }
+    }
+
+    /// Places `bits` with its top-left corner at `(0, 0)`.
+    pub fn from_bits(bits: BitGrid, background: bool) -> Self {
+        let mut ret = Self::new(background);
+        ret.blit(&bits, (0, 0));
+        ret
+    }
+
+    pub fn background(&self) -> bool {
+        self.background
+    }
+
+    /// Inclusive minimum and maximum coordinates of all written cells,
+    /// or `None` if nothing was written yet.
+    pub fn bounds(&self) -> Option<((i64, i64), (i64, i64))> {
+        self.bounds
+    }
+
+    fn contains(&self, pos: (i64, i64)) -> bool {
+        match self.bounds {
+            None => false,
+            Some((min, max)) => {
+                pos.0 >= min.0 && pos.1 >= min.1 && pos.0 <= max.0 && pos.1 <= max.1
+            }
+        }
+    }
+
+    fn local(&self, pos: (i64, i64)) -> (usize, usize) {
+        (
+            (pos.0 - self.origin.0) as usize,
+            (pos.1 - self.origin.1) as usize,
+        )
+    }
+
+    pub fn get(&self, pos: (i64, i64)) -> bool {
+        if self.contains(pos) {
+            self.bits.at(self.local(pos))
+        } else {
+            self.background
+        }
+    }
+
+    pub fn set(&mut self, pos: (i64, i64), value: bool) {
+        self.cover(pos, pos);
+        let pos = self.local(pos);
+        self.bits.set(pos, value);
+    }
+
+    /// Overwrites the cells covered by `bits` placed with its top-left
+    /// corner at `pos`.
+    pub fn blit(&mut self, bits: &BitGrid, pos: (i64, i64)) {
+        if bits.width() == 0 || bits.height() == 0 {
+            return;
+        }
+        let max = (
+            pos.0 + bits.width() as i64 - 1,
+            pos.1 + bits.height() as i64 - 1,
+        );
+        self.cover(pos, max);
+        let pos = self.local(pos);
+        self.bits.blit(bits, pos);
+    }
+
+    /// Copies the cells from `min` to `max`, both inclusive, into a grid
+    /// whose top-left corner holds the cell at `min`.
+    pub fn region(&self, min: (i64, i64), max: (i64, i64)) -> BitGrid {
+        let mut ret = BitGrid::new((max.0 - min.0 + 1) as usize, (max.1 - min.1 + 1) as usize);
+        ret.fill(self.background);
+
+        if let Some((lo, hi)) = self.bounds {
+            let lo = (lo.0.max(min.0), lo.1.max(min.1));
+            let hi = (hi.0.min(max.0), hi.1.min(max.1));
+            if lo.0 <= hi.0 && lo.1 <= hi.1 {
+                let w = (hi.0 - lo.0 + 1) as usize;
+                let h = (hi.1 - lo.1 + 1) as usize;
+                let part = self.bits.cropped(self.local(lo), w, h);
+                ret.blit(&part, ((lo.0 - min.0) as usize, (lo.1 - min.1) as usize));
+            }
+        }
+        ret
+    }
+
+    /// Number of set cells within `bounds`.
+    pub fn count_ones(&self) -> usize {
+        self.bounds
+            .map_or(0, |(min, max)| self.region(min, max).count_ones())
+    }
+
+    /// Copies the cells within `bounds` into a dense array, so that its
+    /// top-left corner holds the cell at the minimum coordinates.
+    pub fn to_array2d(&self) -> Array2D<bool> {
+        match self.bounds {
+            None => Array2D::new(0, 0),
+            Some((min, max)) => self.region(min, max).to_array2d(),
+        }
+    }
+
+    /// Extends `bounds` to include the rectangle from `min` to `max`,
+    /// growing the storage with some slack like `UnboundedGrid` does.
+    fn cover(&mut self, min: (i64, i64), max: (i64, i64)) {
+        let w = self.bits.width() as i64;
+        let h = self.bits.height() as i64;
+        let (x0, y0) = (min.0 - self.origin.0, min.1 - self.origin.1);
+        let (x1, y1) = (max.0 - self.origin.0 + 1, max.1 - self.origin.1 + 1);
+
+        if x0 < 0 || y0 < 0 || x1 > w || y1 > h {
+            let slack_x = (w / 2).max(4);
+            let slack_y = (h / 2).max(4);
+            let (mut lo_x, mut lo_y, mut hi_x, mut hi_y) = if w > 0 && h > 0 {
+                (0, 0, w, h)
+            } else {
+                (x0, y0, x1, y1)
+            };
+            if x0 < lo_x {
+                lo_x = x0 - slack_x;
+            }
+            if y0 < lo_y {
+                lo_y = y0 - slack_y;
+            }
+            if x1 > hi_x {
+                hi_x = x1 + slack_x;
+            }
+            if y1 > hi_y {
+                hi_y = y1 + slack_y;
+            }
+
+            let mut bits = BitGrid::new((hi_x - lo_x) as usize, (hi_y - lo_y) as usize);
+            bits.fill(self.background);
+            if w > 0 && h > 0 {
+                bits.blit(&self.bits, ((-lo_x) as usize, (-lo_y) as usize));
+            }
+            self.origin = (self.origin.0 + lo_x, self.origin.1 + lo_y);
+            self.bits = bits;
+        }
+
+        self.bounds = Some(match self.bounds {
+            None => (min, max),
+            Some((lo, hi)) => (
+                (lo.0.min(min.0), lo.1.min(min.1)),
+                (hi.0.max(max.0), hi.1.max(max.1)),
+            ),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{UnboundedBitGrid, UnboundedGrid};
+    use crate::bitgrid::BitGrid;
+    use crate::grid::Grid;
+
+    #[test]
+    fn test_growth() {
+        let mut grid = UnboundedGrid::new(7);
+        assert_eq!(grid.bounds(), None);
+        assert_eq!(*grid.get((-100, 100)), 7);
+
+        grid.set((2, 3), 1);
+        grid.set((-5, 3), 2);
+        *grid.at_mut((0, -20)) += 1;
+        assert_eq!(grid.bounds(), Some(((-5, -20), (2, 3))));
+
+        assert_eq!(*grid.get((2, 3)), 1);
+        assert_eq!(*grid.get((-5, 3)), 2);
+        assert_eq!(*grid.get((0, -20)), 8);
+        assert_eq!(*grid.get((0, 0)), 7);
+        assert_eq!(*grid.get((3, 3)), 7);
+
+        assert_eq!(grid.iter().count(), 8 * 24);
+        assert_eq!(grid.iter().filter(|v| *v.1 != 7).count(), 3);
+
+        let array = grid.to_array2d();
+        assert_eq!((array.width(), array.height()), (8, 24));
+        assert_eq!(*array.at((7, 23)), 1);
+    }
+
+    #[test]
+    fn test_grid_size() {
+        let mut grid: UnboundedGrid<u32> = Grid::new(4, 3);
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        Grid::set(&mut grid, (9, 1), 5);
+        Grid::set(&mut grid, (1, 1), 2);
+        assert_eq!((grid.width(), grid.height()), (4, 3));
+        let mut occupied = grid.occupied().collect::<Vec<_>>();
+        occupied.sort();
+        assert_eq!(occupied, vec![((1, 1), 2), ((9, 1), 5)]);
+    }
+
+    #[test]
+    fn test_bits() {
+        let mut grid = UnboundedBitGrid::new(true);
+        assert_eq!(grid.bounds(), None);
+        assert!(grid.get((-100, 100)));
+
+        grid.set((2, 3), false);
+        grid.set((-70, 3), false);
+        grid.set((0, -20), true);
+        assert_eq!(grid.bounds(), Some(((-70, -20), (2, 3))));
+        assert!(!grid.get((2, 3)));
+        assert!(!grid.get((-70, 3)));
+        assert!(grid.get((0, -20)));
+        assert!(grid.get((0, 0)));
+        assert!(grid.get((3, 3)));
+        assert_eq!(grid.count_ones(), 73 * 24 - 2);
+
+        let region = grid.region((1, 2), (3, 4));
+        let rows = region
+            .to_array2d()
+            .to_grid_string(|b| if *b { '#' } else { '.' });
+        assert_eq!(rows, "###\n#.#\n###\n");
+
+        let mut bits = BitGrid::new(100, 1);
+        bits.set((99, 0), true);
+        let mut grid = UnboundedBitGrid::new(false);
+        grid.blit(&bits, (-50, -1));
+        grid.blit(&bits, (60, 5));
+        assert_eq!(grid.bounds(), Some(((-50, -1), (159, 5))));
+        assert!(grid.get((49, -1)));
+        assert!(grid.get((159, 5)));
+        assert!(!grid.get((160, 5)));
+        assert_eq!(grid.count_ones(), 2);
+        assert_eq!(grid.to_array2d().width(), 210);
+    }
+}